@@ -0,0 +1,273 @@
+use std::fs;
+use std::path::Path;
+
+const DEPENDABOT_FILES: &[&str] = &[".github/dependabot.yml", ".github/dependabot.yaml"];
+
+const RENOVATE_FILES: &[&str] = &[
+    "renovate.json",
+    "renovate.json5",
+    ".github/renovate.json",
+    ".github/renovate.json5",
+    ".gitlab/renovate.json",
+    ".gitlab/renovate.json5",
+    ".renovaterc",
+    ".renovaterc.json",
+    ".renovaterc.json5",
+];
+
+/// The ecosystems Renovate updates if the configuration does not list the `enabledManagers`.
+const RENOVATE_DEFAULT_ECOSYSTEMS: &[&str] = &["cargo", "github-actions"];
+
+/// The services that send pull-requests to update the dependencies of a repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UpdateBot {
+    Dependabot,
+    Renovate,
+}
+
+impl UpdateBot {
+    pub const ALL: [Self; 2] = [Self::Dependabot, Self::Renovate];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Dependabot => "dependabot",
+            Self::Renovate => "renovate",
+        }
+    }
+
+    const fn files(self) -> &'static [&'static str] {
+        match self {
+            Self::Dependabot => DEPENDABOT_FILES,
+            Self::Renovate => RENOVATE_FILES,
+        }
+    }
+}
+
+/// What the dependency update bots of a repository cover.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UpdateBots {
+    pub bots: Vec<UpdateBot>,
+    /// The `package-ecosystem` entries of Dependabot or the managers of Renovate, e.g. `cargo` and `github-actions`.
+    pub ecosystems: Vec<String>,
+    /// How often the cargo dependencies are updated, e.g. `weekly`. Empty if there is no schedule.
+    pub interval: String,
+}
+
+impl UpdateBots {
+    /// Add the `updates` of a `.github/dependabot.yml` file.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `content` is not a proper YAML file.
+    pub fn add_dependabot(&mut self, content: &str) -> Result<(), String> {
+        let config: serde_yaml::Value =
+            serde_yaml::from_str(content).map_err(|err| err.to_string())?;
+        let updates = config
+            .get("updates")
+            .and_then(serde_yaml::Value::as_sequence);
+        for update in updates.into_iter().flatten() {
+            let Some(ecosystem) = update
+                .get("package-ecosystem")
+                .and_then(serde_yaml::Value::as_str)
+            else {
+                continue;
+            };
+            self.add_ecosystem(ecosystem);
+            if ecosystem == "cargo" {
+                if let Some(interval) = update
+                    .get("schedule")
+                    .and_then(|schedule| schedule.get("interval"))
+                    .and_then(serde_yaml::Value::as_str)
+                {
+                    interval.clone_into(&mut self.interval);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_ecosystem(&mut self, ecosystem: &str) {
+        if !self.ecosystems.iter().any(|name| name == ecosystem) {
+            self.ecosystems.push(ecosystem.to_owned());
+        }
+    }
+
+    /// Add the managers and the schedule of a Renovate configuration file.
+    /// (We parse every file as JSON5, as it is a superset of JSON.)
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `content` is not a proper JSON5 file.
+    pub fn add_renovate(&mut self, content: &str) -> Result<(), String> {
+        let config: serde_json::Value = json5::from_str(content).map_err(|err| err.to_string())?;
+
+        let managers = config
+            .get("enabledManagers")
+            .and_then(serde_json::Value::as_array)
+            .map_or_else(
+                || RENOVATE_DEFAULT_ECOSYSTEMS.to_vec(),
+                |managers| {
+                    managers
+                        .iter()
+                        .filter_map(serde_json::Value::as_str)
+                        .collect()
+                },
+            );
+        for manager in managers {
+            self.add_ecosystem(manager);
+        }
+
+        let schedule = config
+            .get("schedule")
+            .and_then(|schedule| {
+                schedule.as_array().map_or_else(
+                    || schedule.as_str().map(str::to_owned),
+                    |entries| {
+                        Some(
+                            entries
+                                .iter()
+                                .filter_map(serde_json::Value::as_str)
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        )
+                    },
+                )
+            })
+            .or_else(|| {
+                // presets such as "schedule:weekly"
+                config
+                    .get("extends")
+                    .and_then(serde_json::Value::as_array)?
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .find_map(|preset| preset.strip_prefix("schedule:"))
+                    .map(str::to_owned)
+            });
+        if let Some(schedule) = schedule {
+            self.interval = schedule;
+        }
+        Ok(())
+    }
+
+    /// Find and parse the configuration files of the bots among the `files` of the repository in `root`.
+    pub fn from_files(root: &Path, files: &[String]) -> Self {
+        let mut bots = Self::default();
+        for bot in UpdateBot::ALL {
+            for file in files
+                .iter()
+                .filter(|file| bot.files().contains(&file.as_str()))
+            {
+                if !bots.bots.contains(&bot) {
+                    bots.bots.push(bot);
+                }
+                let path = root.join(file);
+                let result = fs::read_to_string(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|content| match bot {
+                        UpdateBot::Dependabot => bots.add_dependabot(&content),
+                        UpdateBot::Renovate => bots.add_renovate(&content),
+                    });
+                if let Err(err) = result {
+                    log::warn!("Could not parse {}: {err}", path.display());
+                    // we don't know what the broken file configures, assume the defaults of Renovate
+                    if bot == UpdateBot::Renovate {
+                        for &ecosystem in RENOVATE_DEFAULT_ECOSYSTEMS {
+                            bots.add_ecosystem(ecosystem);
+                        }
+                    }
+                }
+            }
+        }
+        bots.ecosystems.sort();
+        bots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_dependabot() {
+        let mut bots = UpdateBots::default();
+        bots.add_dependabot(
+            r#"
+version: 2
+updates:
+  - package-ecosystem: "github-actions"
+    directory: "/"
+    schedule:
+      interval: "monthly"
+  - package-ecosystem: "cargo"
+    directory: "/"
+    schedule:
+      interval: "weekly"
+"#,
+        )
+        .unwrap();
+        assert_eq!(bots.ecosystems, vec!["github-actions", "cargo"]);
+        assert_eq!(bots.interval, "weekly");
+
+        bots.add_dependabot("updates: [").unwrap_err();
+    }
+
+    #[test]
+    fn test_add_renovate() {
+        let mut bots = UpdateBots::default();
+        bots.add_renovate(r#"{"extends": ["config:recommended", "schedule:weekly"]}"#)
+            .unwrap();
+        assert_eq!(bots.ecosystems, vec!["cargo", "github-actions"]);
+        assert_eq!(bots.interval, "weekly");
+
+        let mut bots = UpdateBots::default();
+        bots.add_renovate(
+            r#"{"enabledManagers": ["github-actions"], "schedule": ["before 5am on monday"]}"#,
+        )
+        .unwrap();
+        assert_eq!(bots.ecosystems, vec!["github-actions"]);
+        assert_eq!(bots.interval, "before 5am on monday");
+
+        let mut bots = UpdateBots::default();
+        bots.add_renovate(
+            "{\n  // only the actions\n  enabledManagers: ['github-actions',],\n  schedule: 'every weekend',\n}\n",
+        )
+        .unwrap();
+        assert_eq!(bots.ecosystems, vec!["github-actions"]);
+        assert_eq!(bots.interval, "every weekend");
+
+        bots.add_renovate("{ enabledManagers: [").unwrap_err();
+    }
+
+    #[test]
+    fn test_from_files() {
+        let root = crate::temp_folder("update-bots");
+        fs::create_dir_all(root.join(".github")).unwrap();
+        fs::write(
+            root.join(".github/dependabot.yml"),
+            "updates:\n  - package-ecosystem: cargo\n    schedule:\n      interval: daily\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("renovate.json5"),
+            "{ // JSON5\n enabledManagers: ['cargo'] }",
+        )
+        .unwrap();
+
+        let files = [".github/dependabot.yml", "renovate.json5", "src/lib.rs"]
+            .iter()
+            .map(|&file| file.to_owned())
+            .collect::<Vec<_>>();
+        let bots = UpdateBots::from_files(&root, &files);
+        assert_eq!(bots.bots, vec![UpdateBot::Dependabot, UpdateBot::Renovate]);
+        assert_eq!(bots.ecosystems, vec!["cargo"]);
+        assert_eq!(bots.interval, "daily");
+
+        // we assume the defaults of Renovate if we could not parse its configuration
+        fs::write(root.join(".renovaterc.json5"), "{ enabledManagers: [").unwrap();
+        let bots = UpdateBots::from_files(&root, &[".renovaterc.json5".to_owned()]);
+        assert_eq!(bots.ecosystems, vec!["cargo", "github-actions"]);
+
+        assert_eq!(UpdateBots::from_files(&root, &[]), UpdateBots::default());
+    }
+}
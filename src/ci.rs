@@ -0,0 +1,866 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_yaml::{Mapping, Value};
+
+/// Actions that are archived or unmaintained.
+const DEPRECATED_ACTIONS: &[&str] = &[
+    "actions-rs/",
+    "actions/create-release",
+    "actions/upload-release-asset",
+];
+
+/// The owner of the official actions of GitHub.
+const OFFICIAL_ACTIONS: &str = "actions/";
+
+/// The Continuous Integration systems we recognize based on their configuration files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CiProvider {
+    AppVeyor,
+    AzurePipelines,
+    Buildkite,
+    CircleCi,
+    Cirrus,
+    Drone,
+    ForgejoActions,
+    GitHubActions,
+    GitLabPipelines,
+    GiteaActions,
+    Jenkins,
+    /// builds.sr.ht.
+    Sourcehut,
+    Travis,
+    Woodpecker,
+}
+
+impl CiProvider {
+    pub const ALL: [Self; 14] = [
+        Self::GitHubActions,
+        Self::GitLabPipelines,
+        Self::Travis,
+        Self::CircleCi,
+        Self::AzurePipelines,
+        Self::AppVeyor,
+        Self::Cirrus,
+        Self::Woodpecker,
+        Self::Drone,
+        Self::Buildkite,
+        Self::Sourcehut,
+        Self::ForgejoActions,
+        Self::GiteaActions,
+        Self::Jenkins,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::GitHubActions => "github-actions",
+            Self::GitLabPipelines => "gitlab-pipelines",
+            Self::Travis => "travis",
+            Self::CircleCi => "circleci",
+            Self::AzurePipelines => "azure-pipelines",
+            Self::AppVeyor => "appveyor",
+            Self::Cirrus => "cirrus",
+            Self::Woodpecker => "woodpecker",
+            Self::Drone => "drone",
+            Self::Buildkite => "buildkite",
+            Self::Sourcehut => "sourcehut",
+            Self::ForgejoActions => "forgejo-actions",
+            Self::GiteaActions => "gitea-actions",
+            Self::Jenkins => "jenkins",
+        }
+    }
+
+    /// The files in the root of the repository and the folders that hold the configuration of the CI system.
+    const fn config(self) -> (&'static [&'static str], &'static [&'static str]) {
+        match self {
+            Self::GitHubActions => (&[], &[".github/workflows/"]),
+            Self::GitLabPipelines => (&[".gitlab-ci.yml", ".gitlab-ci.yaml"], &[]),
+            Self::Travis => (&[".travis.yml"], &[]),
+            Self::CircleCi => (&[".circleci/config.yml", ".circleci/config.yaml"], &[]),
+            Self::AzurePipelines => (
+                &[
+                    "azure-pipelines.yml",
+                    "azure-pipelines.yaml",
+                    ".azure-pipelines.yml",
+                ],
+                &[".azure-pipelines/"],
+            ),
+            Self::AppVeyor => (&["appveyor.yml", ".appveyor.yml"], &[]),
+            Self::Cirrus => (&[".cirrus.yml", ".cirrus.star"], &[]),
+            Self::Woodpecker => (&[".woodpecker.yml", ".woodpecker.yaml"], &[".woodpecker/"]),
+            Self::Drone => (&[".drone.yml", ".drone.star"], &[]),
+            Self::Buildkite => (&[], &[".buildkite/"]),
+            Self::Sourcehut => (&[".build.yml"], &[".builds/"]),
+            Self::ForgejoActions => (&[], &[".forgejo/workflows/"]),
+            Self::GiteaActions => (&[], &[".gitea/workflows/"]),
+            Self::Jenkins => (&["Jenkinsfile"], &[]),
+        }
+    }
+
+    pub const fn display(self) -> &'static str {
+        match self {
+            Self::GitHubActions => "GitHub Actions",
+            Self::GitLabPipelines => "GitLab Pipelines",
+            Self::Travis => "Travis CI",
+            Self::CircleCi => "CircleCI",
+            Self::AzurePipelines => "Azure Pipelines",
+            Self::AppVeyor => "AppVeyor",
+            Self::Cirrus => "Cirrus CI",
+            Self::Woodpecker => "Woodpecker CI",
+            Self::Drone => "Drone CI",
+            Self::Buildkite => "Buildkite",
+            Self::Sourcehut => "sourcehut builds",
+            Self::ForgejoActions => "Forgejo Actions",
+            Self::GiteaActions => "Gitea Actions",
+            Self::Jenkins => "Jenkins",
+        }
+    }
+
+    /// The CI systems configured among the `files` of the repository.
+    pub fn from_files(files: &[String]) -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|provider| files.iter().any(|file| provider.is_config_file(file)))
+            .collect()
+    }
+
+    /// Is `file` (relative to the root of the repository) a configuration file of this CI system?
+    pub fn is_config_file(self, file: &str) -> bool {
+        let (files, folders) = self.config();
+        files.contains(&file)
+            || folders.iter().any(|folder| {
+                file.strip_prefix(folder).is_some_and(|name| {
+                    Path::new(name).extension().is_some_and(|ext| {
+                        ext.eq_ignore_ascii_case("yml") || ext.eq_ignore_ascii_case("yaml")
+                    })
+                })
+            })
+    }
+}
+
+/// The things we look for in the jobs of the GitHub Actions workflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CiCheck {
+    /// `cargo audit`.
+    Audit,
+    Clippy,
+    /// tarpaulin, llvm-cov, grcov, kcov, Codecov or Coveralls.
+    Coverage,
+    /// `cargo deny`.
+    Deny,
+    Doc,
+    /// `cargo fmt --check`.
+    FmtCheck,
+    Miri,
+    /// A toolchain with a version number, e.g. `1.70`, or `cargo msrv`.
+    Msrv,
+    Nightly,
+    /// `cargo test` or `cargo nextest`.
+    Test,
+}
+
+impl CiCheck {
+    pub const ALL: [Self; 10] = [
+        Self::Test,
+        Self::Clippy,
+        Self::FmtCheck,
+        Self::Doc,
+        Self::Miri,
+        Self::Deny,
+        Self::Audit,
+        Self::Coverage,
+        Self::Msrv,
+        Self::Nightly,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Test => "test",
+            Self::Clippy => "clippy",
+            Self::FmtCheck => "fmt-check",
+            Self::Doc => "doc",
+            Self::Miri => "miri",
+            Self::Deny => "deny",
+            Self::Audit => "audit",
+            Self::Coverage => "coverage",
+            Self::Msrv => "msrv",
+            Self::Nightly => "nightly",
+        }
+    }
+
+    pub const fn display(self) -> &'static str {
+        match self {
+            Self::Test => "cargo test",
+            Self::Clippy => "cargo clippy",
+            Self::FmtCheck => "cargo fmt --check",
+            Self::Doc => "cargo doc",
+            Self::Miri => "Miri",
+            Self::Deny => "cargo deny",
+            Self::Audit => "cargo audit",
+            Self::Coverage => "test coverage",
+            Self::Msrv => "MSRV toolchain",
+            Self::Nightly => "nightly toolchain",
+        }
+    }
+}
+
+/// What the GitHub Actions workflows of a repository run.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Workflows {
+    /// The third-party actions in the `uses` fields of the steps, e.g. `actions/checkout@v4`.
+    pub actions: Vec<String>,
+    pub checks: Vec<CiCheck>,
+    pub cron: Vec<String>,
+    /// `linux`, `windows`, `macos` or the name of any other runner.
+    pub os: Vec<String>,
+}
+
+impl Workflows {
+    /// Check a step with `uses` based on the name of the action and its `with` parameters.
+    fn add_action(&mut self, action: &str, with: Option<&Mapping>) {
+        // local actions and docker images are not versioned by a git reference
+        if !action.starts_with("./") && !action.starts_with("docker://") {
+            self.actions.push(action.to_owned());
+        }
+        let (name, version) = split_action(action);
+        let name = name.to_lowercase();
+        let param = |key: &str| with.and_then(|with| with.get(key)).map(scalar_to_string);
+
+        match name.as_str() {
+            "dtolnay/rust-toolchain" => self.add_toolchain(version),
+            "actions-rs/cargo" => {
+                let command = param("command").unwrap_or_default();
+                let args = param("args").unwrap_or_default();
+                let words = core::iter::once(command.as_str())
+                    .chain(args.split_whitespace())
+                    .collect::<Vec<_>>();
+                self.add_cargo_command(&words);
+            }
+            "actions-rs/clippy-check"
+            | "giraffate/clippy-action"
+            | "clechasseur/rs-clippy-check" => {
+                self.add_check(CiCheck::Clippy);
+            }
+            "actions-rust-lang/rustfmt" | "mbrobbel/rustfmt-check" => {
+                self.add_check(CiCheck::FmtCheck);
+            }
+            "embarkstudios/cargo-deny-action" => self.add_check(CiCheck::Deny),
+            "rustsec/audit-check" | "actions-rs/audit-check" => self.add_check(CiCheck::Audit),
+            "codecov/codecov-action" | "coverallsapp/github-action" | "actions-rs/tarpaulin" => {
+                self.add_check(CiCheck::Coverage);
+            }
+            _ => {}
+        }
+        if let Some(toolchain) = param("toolchain") {
+            self.add_toolchain(&toolchain);
+        }
+    }
+
+    /// The arguments of `cargo`, e.g. `["+nightly", "miri", "test"]`.
+    fn add_cargo_command(&mut self, args: &[&str]) {
+        let args = match args.split_first() {
+            Some((first, rest)) if first.starts_with('+') => {
+                self.add_toolchain(&first[1..]);
+                rest
+            }
+            _ => args,
+        };
+        let Some((subcommand, rest)) = args.split_first() else {
+            return;
+        };
+        let check = match *subcommand {
+            "test" | "nextest" => CiCheck::Test,
+            "clippy" => CiCheck::Clippy,
+            "fmt" if rest.contains(&"--check") => CiCheck::FmtCheck,
+            "doc" | "rustdoc" => CiCheck::Doc,
+            "miri" => CiCheck::Miri,
+            "deny" => CiCheck::Deny,
+            "audit" => CiCheck::Audit,
+            "tarpaulin" | "llvm-cov" => CiCheck::Coverage,
+            "msrv" => CiCheck::Msrv,
+            _ => return,
+        };
+        self.add_check(check);
+    }
+
+    fn add_check(&mut self, check: CiCheck) {
+        if !self.checks.contains(&check) {
+            self.checks.push(check);
+        }
+    }
+
+    fn add_job(&mut self, job: &Value) {
+        let matrix = job
+            .get("strategy")
+            .and_then(|strategy| strategy.get("matrix"))
+            .and_then(Value::as_mapping);
+
+        let runners = job.get("runs-on").map_or_else(Vec::new, |runner| {
+            runner.as_sequence().map_or_else(
+                || resolve_matrix(&scalar_to_string(runner), matrix),
+                |labels| labels.iter().map(scalar_to_string).collect(),
+            )
+        });
+        for runner in runners {
+            let os = os_family(&runner);
+            if !os.is_empty() && !self.os.contains(&os) {
+                self.os.push(os);
+            }
+        }
+
+        if let Some(matrix) = matrix {
+            for key in matrix.keys().filter_map(Value::as_str) {
+                let key = key.to_lowercase();
+                if key.contains("msrv") {
+                    self.add_check(CiCheck::Msrv);
+                }
+                if key.contains("rust") || key.contains("toolchain") || key.contains("channel") {
+                    for toolchain in matrix_values(matrix, &key) {
+                        self.add_toolchain(&toolchain);
+                    }
+                }
+            }
+        }
+
+        let steps = job.get("steps").and_then(Value::as_sequence);
+        for step in steps.into_iter().flatten() {
+            if let Some(script) = step.get("run").and_then(Value::as_str) {
+                self.add_script(script);
+            }
+            if let Some(action) = step.get("uses").and_then(Value::as_str) {
+                self.add_action(action, step.get("with").and_then(Value::as_mapping));
+            }
+        }
+    }
+
+    /// Check a shell script in a `run` step, one command at a time.
+    fn add_script(&mut self, script: &str) {
+        for command in script.split(['\n', ';', '|', '&']) {
+            let words = command.split_whitespace().collect::<Vec<_>>();
+            if let Some((&"cargo", args)) = words.split_first() {
+                self.add_cargo_command(args);
+                continue;
+            }
+            match words.as_slice() {
+                &["rustup", "toolchain", "install", toolchain, ..]
+                | &["rustup", "install" | "default", toolchain, ..]
+                | &["rustup", "override", "set", toolchain, ..] => self.add_toolchain(toolchain),
+                &["grcov" | "kcov", ..] => self.add_check(CiCheck::Coverage),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_toolchain(&mut self, toolchain: &str) {
+        if toolchain.starts_with("nightly") {
+            self.add_check(CiCheck::Nightly);
+        }
+        if toolchain.starts_with(|char: char| char.is_ascii_digit()) {
+            self.add_check(CiCheck::Msrv);
+        }
+    }
+
+    /// Add the jobs and the schedule of the workflow in `content`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `content` is not a proper YAML file.
+    pub fn add_workflow(&mut self, content: &str) -> Result<(), String> {
+        let workflow: Value = serde_yaml::from_str(content).map_err(|err| err.to_string())?;
+
+        let schedule = workflow
+            .get("on")
+            .and_then(|on| on.get("schedule"))
+            .and_then(Value::as_sequence);
+        for entry in schedule.into_iter().flatten() {
+            if let Some(cron) = entry.get("cron").and_then(Value::as_str) {
+                self.cron.push(cron.to_owned());
+            }
+        }
+
+        let jobs = workflow.get("jobs").and_then(Value::as_mapping);
+        for job in jobs.into_iter().flat_map(Mapping::values) {
+            self.add_job(job);
+        }
+        Ok(())
+    }
+
+    /// Parse the GitHub Actions workflows among the `files` of the repository in `root`.
+    pub fn from_files(root: &Path, files: &[String]) -> Self {
+        let mut workflows = Self::default();
+        for file in files
+            .iter()
+            .filter(|file| CiProvider::GitHubActions.is_config_file(file))
+        {
+            let path = root.join(file);
+            let result = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| workflows.add_workflow(&content));
+            if let Err(err) = result {
+                log::warn!("Could not parse {}: {err}", path.display());
+            }
+        }
+        workflows.checks.sort_by_key(|check| {
+            CiCheck::ALL
+                .iter()
+                .position(|other| other == check)
+                .unwrap_or_default()
+        });
+        workflows.os.sort();
+        workflows.actions.sort();
+        workflows.actions.dedup();
+        workflows
+    }
+}
+
+/// How the `uses` field of a step selects the version of the action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Pinning {
+    /// A branch, or no reference at all.
+    Branch,
+    /// A full commit SHA, the only reference that cannot be moved.
+    Sha,
+    Tag,
+}
+
+impl Pinning {
+    pub const ALL: [Self; 3] = [Self::Sha, Self::Tag, Self::Branch];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Sha => "sha",
+            Self::Tag => "tag",
+            Self::Branch => "branch",
+        }
+    }
+
+    /// We have no access to the repository of the action so anything that looks like a version number is a tag.
+    pub fn from_version(version: &str) -> Self {
+        if version.len() == 40 && version.chars().all(|char| char.is_ascii_hexdigit()) {
+            return Self::Sha;
+        }
+        if version
+            .trim_start_matches('v')
+            .starts_with(|char: char| char.is_ascii_digit())
+        {
+            return Self::Tag;
+        }
+        Self::Branch
+    }
+}
+
+/// The problems we report about the third-party actions used in the workflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ActionWarning {
+    /// The action is used from a branch, so any push to that branch changes what runs in the CI.
+    Branch,
+    /// The action is archived or no longer maintained, e.g. `actions-rs/*`.
+    Deprecated,
+    /// An old major version of an official action, e.g. `actions/checkout@v2`.
+    /// (See `ActionWarning::is_outdated`, it depends on the actions of all the crates.)
+    Outdated,
+}
+
+impl ActionWarning {
+    pub const ALL: [Self; 3] = [Self::Branch, Self::Deprecated, Self::Outdated];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Branch => "branch",
+            Self::Deprecated => "deprecated",
+            Self::Outdated => "outdated",
+        }
+    }
+
+    /// The problems with the `uses` field of a step, e.g. `actions-rs/toolchain@v1`.
+    /// (Except `Outdated`, we can only tell that once we have seen the actions of all the crates.)
+    pub fn from_action(action: &str) -> Vec<Self> {
+        let (name, version) = split_action(action);
+        let name = name.to_lowercase();
+        let mut warnings = vec![];
+        if Pinning::from_version(version) == Pinning::Branch {
+            warnings.push(Self::Branch);
+        }
+        if DEPRECATED_ACTIONS
+            .iter()
+            .any(|&deprecated| name.starts_with(deprecated))
+        {
+            warnings.push(Self::Deprecated);
+        }
+        warnings
+    }
+
+    /// An official action older than the newest major version in `latest`. (See `latest_official_actions`.)
+    pub fn is_outdated(action: &str, latest: &HashMap<String, u32>) -> bool {
+        let (name, version) = split_action(action);
+        match (latest.get(&name.to_lowercase()), major_version(version)) {
+            (Some(&latest), Some(major)) => major < latest,
+            _ => false,
+        }
+    }
+}
+
+/// The newest major version of each official action in `actions`, e.g. `actions/checkout` => 5.
+///
+/// We have no access to the releases of the actions, but some crates always use the newest one,
+/// so we don't need to update a list by hand.
+pub fn latest_official_actions<'action, I>(actions: I) -> HashMap<String, u32>
+where
+    I: IntoIterator<Item = &'action String>,
+{
+    let mut latest: HashMap<String, u32> = HashMap::new();
+    for action in actions {
+        let (name, version) = split_action(action);
+        let name = name.to_lowercase();
+        if !name.starts_with(OFFICIAL_ACTIONS) {
+            continue;
+        }
+        if let Some(major) = major_version(version) {
+            let newest = latest.entry(name).or_insert(major);
+            *newest = (*newest).max(major);
+        }
+    }
+    latest
+}
+
+/// The major version of a tag, e.g. 4 for `v4.1.2`. `None` for a branch or a SHA.
+fn major_version(version: &str) -> Option<u32> {
+    if Pinning::from_version(version) != Pinning::Tag {
+        return None;
+    }
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u32>().ok())
+}
+
+/// The name and the version of an action, e.g. `("actions/checkout", "v4")`.
+pub fn split_action(action: &str) -> (&str, &str) {
+    action.split_once('@').unwrap_or((action, ""))
+}
+
+fn scalar_to_string(value: &Value) -> String {
+    if let Some(text) = value.as_str() {
+        return text.to_owned();
+    }
+    if value.is_number() || value.is_bool() {
+        return serde_yaml::to_string(value)
+            .unwrap_or_default()
+            .trim_end()
+            .to_owned();
+    }
+    String::new()
+}
+
+/// All the values of `key` in the `matrix`, including the ones in `include`.
+fn matrix_values(matrix: &Mapping, key: &str) -> Vec<String> {
+    let mut values = matrix
+        .get(key)
+        .and_then(Value::as_sequence)
+        .map(|values| values.iter().map(scalar_to_string).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let include = matrix.get("include").and_then(Value::as_sequence);
+    values.extend(
+        include
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.get(key))
+            .map(scalar_to_string),
+    );
+    values
+}
+
+/// The values of `${{ matrix.NAME }}` or the `value` itself if it is not such an expression.
+fn resolve_matrix(value: &str, matrix: Option<&Mapping>) -> Vec<String> {
+    let name = value
+        .strip_prefix("${{")
+        .and_then(|expression| expression.strip_suffix("}}"))
+        .and_then(|expression| expression.trim().strip_prefix("matrix."));
+    match (name, matrix) {
+        (Some(name), Some(matrix)) => matrix_values(matrix, name),
+        (Some(_), None) => vec![],
+        (None, _) => vec![value.to_owned()],
+    }
+}
+
+fn os_family(runner: &str) -> String {
+    let runner = runner.trim().to_lowercase();
+    if runner.starts_with("ubuntu") {
+        return String::from("linux");
+    }
+    if runner.starts_with("windows") {
+        return String::from("windows");
+    }
+    if runner.starts_with("macos") {
+        return String::from("macos");
+    }
+    // the value of an expression we could not resolve
+    if runner.contains("${{") {
+        return String::new();
+    }
+    runner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORKFLOW: &str = r#"
+name: CI
+on:
+  push:
+  schedule:
+    - cron: "0 3 * * 1"
+jobs:
+  test:
+    strategy:
+      matrix:
+        os: [ubuntu-latest, windows-latest]
+        rust: [stable, "1.70"]
+        include:
+          - os: macos-14
+            rust: stable
+    runs-on: ${{ matrix.os }}
+    steps:
+      - uses: actions/checkout@v4
+      - uses: dtolnay/rust-toolchain@master
+        with:
+          toolchain: ${{ matrix.rust }}
+      - run: cargo test --all-features
+      - run: |
+          cargo fmt --all -- --check
+          cargo clippy -- -D warnings
+  miri:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: dtolnay/rust-toolchain@nightly
+      - run: cargo +nightly miri test
+      - uses: EmbarkStudios/cargo-deny-action@v1
+"#;
+
+    fn files(files: &[&str]) -> Vec<String> {
+        files.iter().map(|&file| file.to_owned()).collect()
+    }
+
+    fn checks(workflows: &Workflows) -> Vec<&str> {
+        workflows
+            .checks
+            .iter()
+            .map(|check| check.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_add_workflow() {
+        let mut workflows = Workflows::default();
+        workflows.add_workflow(WORKFLOW).unwrap();
+        assert_eq!(
+            checks(&workflows),
+            vec![
+                "msrv",
+                "test",
+                "fmt-check",
+                "clippy",
+                "nightly",
+                "miri",
+                "deny"
+            ]
+        );
+        assert_eq!(workflows.os, vec!["linux", "windows", "macos"]);
+        assert_eq!(workflows.cron, vec!["0 3 * * 1"]);
+
+        workflows.add_workflow("jobs: [").unwrap_err();
+    }
+
+    #[test]
+    fn test_add_workflow_actions_rs() {
+        let mut workflows = Workflows::default();
+        workflows
+            .add_workflow(
+                "
+on: [push]
+jobs:
+  check:
+    runs-on: [self-hosted, ARM64]
+    steps:
+      - uses: actions-rs/toolchain@v1
+        with:
+          toolchain: 1.56.0
+      - uses: actions-rs/cargo@v1
+        with:
+          command: fmt
+          args: --all -- --check
+      - run: cargo doc --no-deps && cargo tarpaulin
+",
+            )
+            .unwrap();
+        assert_eq!(
+            checks(&workflows),
+            vec!["msrv", "fmt-check", "doc", "coverage"]
+        );
+        assert_eq!(workflows.os, vec!["self-hosted", "arm64"]);
+        assert!(workflows.cron.is_empty());
+    }
+
+    #[test]
+    fn test_actions() {
+        let mut workflows = Workflows::default();
+        workflows
+            .add_workflow(
+                "
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - uses: ./.github/actions/setup
+      - uses: docker://alpine:3.19
+      - uses: actions/checkout@v4
+",
+            )
+            .unwrap();
+        assert_eq!(
+            workflows.actions,
+            vec!["actions/checkout@v4", "actions/checkout@v4"]
+        );
+    }
+
+    #[test]
+    fn test_pinning() {
+        assert_eq!(
+            Pinning::from_version("b4ffde65f46336ab88eb53be808477a3936bae11"),
+            Pinning::Sha
+        );
+        assert_eq!(Pinning::from_version("v4"), Pinning::Tag);
+        assert_eq!(Pinning::from_version("1.2.3"), Pinning::Tag);
+        assert_eq!(Pinning::from_version("b4ffde6"), Pinning::Branch);
+        assert_eq!(Pinning::from_version("master"), Pinning::Branch);
+        assert_eq!(Pinning::from_version(""), Pinning::Branch);
+    }
+
+    #[test]
+    fn test_action_warnings() {
+        assert_eq!(ActionWarning::from_action("actions/checkout@v4"), vec![]);
+        assert_eq!(
+            ActionWarning::from_action("actions/checkout@v3.5.2"),
+            vec![]
+        );
+        assert_eq!(
+            ActionWarning::from_action("actions-rs/toolchain@v1"),
+            vec![ActionWarning::Deprecated]
+        );
+        assert_eq!(
+            ActionWarning::from_action("actions-rs/cargo@master"),
+            vec![ActionWarning::Branch, ActionWarning::Deprecated]
+        );
+        assert_eq!(
+            ActionWarning::from_action("dtolnay/rust-toolchain@stable"),
+            vec![ActionWarning::Branch]
+        );
+        assert_eq!(
+            ActionWarning::from_action("actions/checkout@b4ffde65f46336ab88eb53be808477a3936bae11"),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_outdated_actions() {
+        let actions = [
+            "actions/checkout@v3.5.2",
+            "actions/checkout@v5",
+            "Actions/Cache@v4",
+            "actions/cache@main",
+            "actions/upload-artifact@b4ffde65f46336ab88eb53be808477a3936bae11",
+            "dtolnay/rust-toolchain@v1",
+        ]
+        .map(str::to_owned);
+        let latest = latest_official_actions(&actions);
+        assert_eq!(
+            latest,
+            HashMap::from([
+                ("actions/checkout".to_owned(), 5),
+                ("actions/cache".to_owned(), 4)
+            ])
+        );
+
+        assert!(ActionWarning::is_outdated(
+            "actions/checkout@v3.5.2",
+            &latest
+        ));
+        assert!(ActionWarning::is_outdated("actions/checkout@v4", &latest));
+        assert!(!ActionWarning::is_outdated(
+            "actions/checkout@v5.0.1",
+            &latest
+        ));
+        assert!(!ActionWarning::is_outdated(
+            "actions/checkout@main",
+            &latest
+        ));
+        assert!(ActionWarning::is_outdated("actions/cache@v2", &latest));
+        assert!(!ActionWarning::is_outdated(
+            "actions/setup-node@v1",
+            &latest
+        ));
+        assert!(!ActionWarning::is_outdated(
+            "dtolnay/rust-toolchain@v0",
+            &latest
+        ));
+    }
+
+    #[test]
+    fn test_add_script() {
+        let mut workflows = Workflows::default();
+        workflows.add_script("cargo fmt\nrustup toolchain install nightly --profile minimal");
+        assert_eq!(checks(&workflows), vec!["nightly"]);
+    }
+
+    #[test]
+    fn test_from_files() {
+        assert_eq!(
+            CiProvider::from_files(&files(&["Cargo.toml", "src/main.rs", "README.md"])),
+            vec![]
+        );
+        assert_eq!(
+            CiProvider::from_files(&files(&[
+                ".github/workflows/ci.yml",
+                ".github/dependabot.yml",
+                ".travis.yml",
+                "Jenkinsfile",
+            ])),
+            vec![
+                CiProvider::GitHubActions,
+                CiProvider::Travis,
+                CiProvider::Jenkins
+            ]
+        );
+        assert_eq!(
+            CiProvider::from_files(&files(&[
+                ".builds/linux.yml",
+                ".woodpecker/test.yaml",
+                ".forgejo/workflows/test.yml",
+                ".gitea/workflows/README.md",
+            ])),
+            vec![
+                CiProvider::Woodpecker,
+                CiProvider::Sourcehut,
+                CiProvider::ForgejoActions
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_config_file() {
+        assert!(CiProvider::GitLabPipelines.is_config_file(".gitlab-ci.yml"));
+        assert!(!CiProvider::GitLabPipelines.is_config_file("sub/.gitlab-ci.yml"));
+        assert!(CiProvider::CircleCi.is_config_file(".circleci/config.yml"));
+        assert!(CiProvider::Buildkite.is_config_file(".buildkite/pipeline.yml"));
+        assert!(!CiProvider::Buildkite.is_config_file(".buildkite/hooks/pre-command"));
+        assert!(CiProvider::AzurePipelines.is_config_file(".azure-pipelines/ci.yml"));
+    }
+}
@@ -2,19 +2,73 @@ use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::Path;
-use std::process::Command;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
 
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 
 mod macros;
 use macros::ok_or_exit;
 
-use rust_digger::{get_owner_and_repo, get_repos_folder, load_details, read_crates, Crate};
+use rust_digger::package::{
+    fetch_package, get_packages_folder, DEFAULT_CRATES_URL, SOURCE_PACKAGE,
+};
+use rust_digger::vcs_backend::Vcs;
+use rust_digger::{
+    get_clone_url, get_crate_subdir, get_owner_and_repo, get_repos_folder, git_command,
+    load_details, load_package_details, read_crates, read_latest_versions, run_command,
+    save_details, save_package_details, Crate, Details, GitError, Version,
+};
+
+/// The configuration file with the defaults of some of the command line options.
+const CONFIG_FILE: &str = "rust-digger.toml";
+
+/// The folders in the root of the repository a sparse checkout includes besides the files in the root and the
+/// folder of the crate: the CI and bot configuration, the cargo configuration and the REUSE license files.
+/// The folders of the other crates of a workspace are left out.
+const SPARSE_FOLDERS: &[&str] = &[".cargo", ".github", ".gitlab", "LICENSES"];
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum CloneStrategy {
+    /// Clone the history without the content of files (--filter=blob:none). Blobs are fetched on checkout.
+    Blobless,
+    #[default]
+    /// Clone the whole history with all the files.
+    Full,
+    /// Clone only the latest commit (--depth 1). The vcs stage fetches the rest of the history metadata.
+    Shallow,
+    /// Blobless clone that checks out only the root, the .github folder and the folder of the crate.
+    Sparse,
+}
+
+impl CloneStrategy {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Shallow => "shallow",
+            Self::Blobless => "blobless",
+            Self::Sparse => "sparse",
+        }
+    }
+
+    const fn git_options(self) -> &'static [&'static str] {
+        match self {
+            Self::Full => &[],
+            Self::Shallow => &["--depth", "1"],
+            Self::Blobless => &["--filter=blob:none"],
+            Self::Sparse => &["--filter=blob:none", "--sparse"],
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[command(version)]
+#[expect(
+    clippy::arbitrary_source_item_ordering,
+    reason = "the fields are in the order of the --help output"
+)]
 struct Cli {
     #[arg(
         long,
@@ -33,9 +87,113 @@ struct Cli {
     #[arg(
         long,
         default_value_t = false,
-        help = "Try to clone even if it already failed once."
+        help = "Try to clone even if it failed recently and pull even if the remote HEAD has not changed."
     )]
     force: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How to clone new repositories. [default: the `strategy` in the config file or full]"
+    )]
+    strategy: Option<CloneStrategy>,
+
+    #[arg(
+        long,
+        help = "Abort cloning a repository when it grows beyond this many MB. (0 means no limit) [default: the `max_size` in the config file or 0]"
+    )]
+    max_size: Option<u64>,
+
+    #[arg(
+        long,
+        default_value_t = CONFIG_FILE.to_owned(),
+        help = "The configuration file. Its `[clone]` table can set the defaults of --strategy and --max-size."
+    )]
+    config: String,
+
+    #[arg(
+        long,
+        default_value_t = 1800,
+        help = "Kill `git clone` after this many seconds. (0 means no limit)"
+    )]
+    clone_timeout: u64,
+
+    #[arg(
+        long,
+        default_value_t = 300,
+        help = "Kill `git pull` after this many seconds. (0 means no limit)"
+    )]
+    pull_timeout: u64,
+
+    #[arg(
+        long,
+        default_value_t = 60,
+        help = "Kill `git ls-remote` after this many seconds. (0 means no limit)"
+    )]
+    ls_remote_timeout: u64,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also fetch the published package of the crates that have no usable repository."
+    )]
+    packages: bool,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Fetch the published package of the crates that have a repository as well, so rust-digger-vcs can compare the two."
+    )]
+    compare_packages: bool,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_CRATES_URL.to_owned(),
+        help = "Where to download the .crate files from. Either a URL or a local folder with the same layout."
+    )]
+    crates_url: String,
+}
+
+impl Cli {
+    /// Use the values of the configuration file for the options missing from the command line.
+    fn apply_config(&mut self, config: &CloneConfig) {
+        self.strategy = self.strategy.or(config.strategy);
+        self.max_size = self.max_size.or(config.max_size);
+    }
+
+    fn max_size(&self) -> u64 {
+        self.max_size.unwrap_or_default()
+    }
+
+    fn strategy(&self) -> CloneStrategy {
+        self.strategy.unwrap_or_default()
+    }
+}
+
+/// The `[clone]` table of the configuration file.
+#[derive(Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+struct CloneConfig {
+    max_size: Option<u64>,
+    strategy: Option<CloneStrategy>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct ConfigFile {
+    #[serde(default)]
+    clone: CloneConfig,
+}
+
+/// Read the `[clone]` table of the configuration file at `path`. A missing file is the same as an empty one.
+fn load_config(path: &Path) -> Result<CloneConfig, String> {
+    if !path.exists() {
+        return Ok(CloneConfig::default());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+    toml::from_str::<ConfigFile>(&content)
+        .map(|config| config.clone)
+        .map_err(|err| format!("Invalid config file {}: {err}", path.display()))
 }
 
 /// for each crate
@@ -57,22 +215,32 @@ struct Cli {
 ///
 ///     (if the data collection takes too long we might need to separate it from the cloning)
 fn main() {
-    let args = Cli::parse();
+    let mut args = Cli::parse();
     simple_logger::init_with_level(log::Level::Info).unwrap();
+    let config = ok_or_exit!(load_config(Path::new(&args.config)), 2);
+    args.apply_config(&config);
     let start_time = std::time::Instant::now();
 
     log::info!("Starting the clone process {}", args.limit);
 
     let crates: Vec<Crate> = ok_or_exit!(read_crates(0), 2);
-    update_repositories(&crates, args.limit, args.recent, args.force);
+    update_repositories(&crates, &args);
+    if args.packages || args.compare_packages {
+        let versions = ok_or_exit!(read_latest_versions(), 2);
+        update_packages(&crates, &versions, &args);
+    }
     log::info!("Elapsed time: {} sec.", start_time.elapsed().as_secs());
     log::info!("Ending the clone process");
 }
 
-fn update_repositories(crates: &Vec<Crate>, limit: u32, recent: u32, force: bool) {
+fn update_repositories(crates: &Vec<Crate>, args: &Cli) {
     log::info!("start update repositories");
+    let limit = args.limit;
+    let recent = args.recent;
 
-    let mut repo_reuse: HashMap<String, i32> = HashMap::new(); // number of times each repository is used for crates (monorepo)
+    // number of times each repository is used for crates (monorepo), keyed by (host, owner, repo)
+    // as the URLs of the crates might point to their folders in the repository
+    let mut repo_reuse: HashMap<(String, String, String), i32> = HashMap::new();
     let now: DateTime<Utc> = Utc::now();
     let before: DateTime<Utc> = now - Duration::try_days(recent as i64).unwrap();
     log::info!("before: {}", before);
@@ -84,26 +252,8 @@ fn update_repositories(crates: &Vec<Crate>, limit: u32, recent: u32, force: bool
         }
         //log::info!("update_at {}", krate.updated_at); // 2023-09-18 01:44:10.299066
         log::info!("Crate {} updated_at: {}", krate.name, krate.updated_at);
-        if 0 < recent {
-            let updated_at =
-                match NaiveDateTime::parse_from_str(&krate.updated_at, "%Y-%m-%d %H:%M:%S.%f") {
-                    Ok(ts) => ts,
-                    Err(err) => {
-                        // TODO there are some crates, eg. one called cargo-script where the
-                        // updated_at field has no microseconds and it looks like this: 2023-09-18 01:44:10
-                        log::error!(
-                            "Error parsing timestamp '{}' of the crate {} ({})",
-                            &krate.updated_at,
-                            &krate.name,
-                            err
-                        );
-                        //std::process::exit(1);
-                        continue;
-                    }
-                };
-            if updated_at < before.naive_utc() {
-                continue;
-            }
+        if 0 < recent && !released_after(krate, before) {
+            continue;
         }
 
         if krate.repository.is_empty() {
@@ -111,108 +261,315 @@ fn update_repositories(crates: &Vec<Crate>, limit: u32, recent: u32, force: bool
         }
 
         let repository = krate.repository.to_lowercase();
-        *repo_reuse.entry(repository.clone()).or_insert(0) += 1;
-        if *repo_reuse.get(&repository as &str).unwrap() > 1 {
-            continue;
-        }
-
         let (host, owner, repo) = get_owner_and_repo(&repository);
         if owner.is_empty() {
             continue;
         }
+        let reuse = repo_reuse
+            .entry((host.clone(), owner.clone(), repo.clone()))
+            .or_insert(0);
+        *reuse += 1;
+        if 1 < *reuse {
+            continue;
+        }
 
-        let details = load_details(&repository);
-        if !details.git_clone_error.is_empty() && !force {
+        let mut details = load_details(&repository);
+        if !args.force && !details.clone_retry_due(now) {
+            log::info!(
+                "Skipping '{}' it failed {} times, last time at {} ({})",
+                krate.repository,
+                details.git_clone_attempts,
+                details.git_clone_error_at,
+                details.git_clone_error
+            );
             continue;
         }
 
         log::info!("update ({count}/{limit}) repository '{}'", krate.repository);
         let owner_path = get_repos_folder().join(host).join(owner);
-        let current_dir = env::current_dir().unwrap();
         log::info!(
             "Creating owner_path {:?} while current_dir is {:?}",
             &owner_path,
-            &current_dir
+            env::current_dir().unwrap()
         );
         fs::create_dir_all(&owner_path).unwrap();
-        let repo_path = owner_path.join(&repo);
-        let status = check_url(&krate.repository);
-        if status != 200 {
-            log::error!(
-                "Error accessing the repository '{}' status: {}",
-                &krate.repository,
-                status
+        let vcs = Vcs::from_repository(&krate.repository);
+        let Some(clone_url) = get_clone_url(&krate.repository) else {
+            log::warn!(
+                "Skipping '{}': we don't know how to clone it",
+                krate.repository
             );
             continue;
-        }
-        if Path::new(&repo_path).exists() {
-            log::info!("repo exist; cd to {:?}", &repo_path);
-            env::set_current_dir(&repo_path).unwrap();
-            git_pull();
-        } else {
-            log::info!("new repo; cd to {:?}", &owner_path);
-            env::set_current_dir(owner_path).unwrap();
-            git_clone(&krate.repository, &repo);
+        };
+        log::info!("Checking the HEAD of '{clone_url}' ({})", vcs.as_str());
+        let remote = match vcs.remote_head(&clone_url, args.ls_remote_timeout) {
+            Ok(remote) => remote,
+            Err(err) => {
+                // we can't tell if it moved
+                details.redirected_url.clear();
+                log::error!(
+                    "Error accessing the repository '{}' {}",
+                    &clone_url,
+                    err.as_str()
+                );
+                details.record_clone_error(err, Utc::now());
+                save_details(&repository, &details);
+                continue;
+            }
+        };
+        record_redirect(&krate.repository, remote.redirected_url, &mut details);
+        if !args.force
+            && !remote.head.is_empty()
+            && owner_path.join(&repo).exists()
+            && details.remote_head == remote.head
+        {
+            log::info!("HEAD of '{clone_url}' has not changed since the last pull");
+            save_details(&repository, &details);
+            continue;
         }
 
-        env::set_current_dir(current_dir).unwrap();
+        clone_or_pull(
+            vcs,
+            &krate.repository,
+            &clone_url,
+            &owner_path,
+            &repo,
+            &mut details,
+            args,
+        );
+        if details.git_clone_error.is_empty() {
+            details.remote_head = remote.head;
+        }
+        save_details(&repository, &details);
         count += 1;
     }
+
+    // rust-digger-vcs records which of these crates are members of the same workspace
+    let monorepos = repo_reuse.values().filter(|&&crates| 1 < crates).count();
+    log::info!("{monorepos} repositories are used by more than one crate");
 }
 
-fn git_clone(url: &str, path: &str) {
-    log::info!("git clone {} {}", url, path);
-    match Command::new("git").arg("clone").arg(url).arg(path).output() {
-        Ok(result) => {
-            if result.status.success() {
-                log::info!("git_clone exit code: '{}'", result.status);
-            } else {
-                log::warn!(
-                    "git_clone exit code: '{}' for url '{}' cloning to '{}'",
-                    result.status,
-                    url,
-                    path
-                );
+/// Unpack the latest published version of each crate that has no repository we could clone
+/// (or of every crate if we want to compare them with the repositories).
+fn update_packages(crates: &Vec<Crate>, versions: &HashMap<String, Version>, args: &Cli) {
+    log::info!("start update packages");
+    let before: DateTime<Utc> = Utc::now() - Duration::try_days(args.recent as i64).unwrap();
+
+    let mut count: u32 = 0;
+    for krate in crates {
+        if 0 < args.limit && args.limit <= count {
+            break;
+        }
+        if 0 < args.recent && !released_after(krate, before) {
+            continue;
+        }
+        if !args.compare_packages && has_repository(krate) {
+            continue;
+        }
+
+        let Some(version) = versions.get(&krate.id) else {
+            log::warn!("No version found for crate {}", krate.name);
+            continue;
+        };
+
+        let mut details = load_package_details(&krate.name);
+        if !args.force && details.package_version == version.num {
+            log::info!("Package {} {} was already fetched", krate.name, version.num);
+            continue;
+        }
+
+        log::info!("update ({count}/{}) package {}", args.limit, krate.name);
+        match fetch_package(
+            &get_packages_folder(),
+            &args.crates_url,
+            &krate.name,
+            &version.num,
+        ) {
+            Ok(_) => {
+                version.num.clone_into(&mut details.package_version);
+                SOURCE_PACKAGE.clone_into(&mut details.source);
+                save_package_details(&krate.name, &details);
             }
+            Err(err) => log::error!("{err}"),
         }
-        Err(err) => log::error!("Could not run git_clone {url} {path} error: {err}"),
+        count += 1;
     }
 }
 
-fn check_url(url: &str) -> reqwest::StatusCode {
-    log::info!("Checking url {}", url);
+/// A crate has a repository if we could clone it (or have not tried yet).
+fn has_repository(krate: &Crate) -> bool {
+    if krate.repository.is_empty() {
+        return false;
+    }
+    let (_host, owner, _repo) = get_owner_and_repo(&krate.repository);
+    if owner.is_empty() {
+        return false;
+    }
+    load_details(&krate.repository).git_clone_error.is_empty()
+}
 
-    let res = match reqwest::blocking::get(url) {
-        Ok(res) => res,
+/// Forges redirect renamed and transferred repositories and git follows the redirect.
+/// We record the new URL so we can tell the maintainers to update the `repository` field.
+fn record_redirect(repository: &str, redirected_url: Option<String>, details: &mut Details) {
+    match redirected_url {
+        Some(url) => {
+            log::warn!("Repository '{repository}' moved to '{url}'");
+            details.redirected_url = url;
+        }
+        None => details.redirected_url.clear(),
+    }
+}
+
+fn released_after(krate: &Crate, before: DateTime<Utc>) -> bool {
+    let updated_at = match NaiveDateTime::parse_from_str(&krate.updated_at, "%Y-%m-%d %H:%M:%S.%f")
+    {
+        Ok(ts) => ts,
         Err(err) => {
-            log::error!("Could not get '{}': {}", url, err);
-            return reqwest::StatusCode::INTERNAL_SERVER_ERROR;
+            // TODO there are some crates, eg. one called cargo-script where the
+            // updated_at field has no microseconds and it looks like this: 2023-09-18 01:44:10
+            log::error!(
+                "Error parsing timestamp '{}' of the crate {} ({})",
+                &krate.updated_at,
+                &krate.name,
+                err
+            );
+            //std::process::exit(1);
+            return false;
         }
     };
-    log::info!("Status: {}", res.status());
-    res.status()
+    before.naive_utc() <= updated_at
 }
 
-fn git_pull() {
-    log::info!("git pull");
+/// Clone the repository into `owner_path` or update the existing clone and record the outcome in the details.
+fn clone_or_pull(
+    vcs: Vcs,
+    repository: &str,
+    url: &str,
+    owner_path: &Path,
+    repo: &str,
+    details: &mut Details,
+    args: &Cli,
+) {
     let current_dir = env::current_dir().unwrap();
+    let repo_path = owner_path.join(repo);
+    let is_new = !repo_path.exists();
+    // the strategies are only implemented for git
+    let strategy = if vcs == Vcs::Git {
+        args.strategy()
+    } else {
+        CloneStrategy::Full
+    };
+    let result = if is_new {
+        log::info!("new repo; cd to {:?}", &owner_path);
+        env::set_current_dir(owner_path).unwrap();
+        clone(vcs, strategy, url, repo, repository, args)
+    } else {
+        log::info!("repo exist; cd to {:?}", &repo_path);
+        env::set_current_dir(&repo_path).unwrap();
+        let added = if details.clone_strategy == CloneStrategy::Sparse.as_str() {
+            git_sparse_checkout_add(Path::new("."), repository, args.pull_timeout)
+        } else {
+            Ok(())
+        };
+        log::info!("{} update", vcs.as_str());
+        added.and_then(|()| vcs.update(Path::new("."), args.pull_timeout))
+    };
 
-    match Command::new("git").arg("pull").output() {
-        Ok(result) => {
-            if result.status.success() {
-                log::info!(
-                    "git_pull exit code: '{}' in folder {:?}",
-                    result.status,
-                    current_dir
-                );
-            } else {
-                log::warn!(
-                    "git_pull exit code: '{}' in folder {:?}",
-                    result.status,
-                    current_dir
-                );
+    env::set_current_dir(current_dir).unwrap();
+    match result {
+        Ok(()) => {
+            details.clear_clone_error();
+            if is_new {
+                vcs.as_str().clone_into(&mut details.vcs);
+                strategy.as_str().clone_into(&mut details.clone_strategy);
             }
         }
-        Err(err) => log::error!("Could not run git_pull in folder {current_dir:?} error: {err}"),
+        Err(err) => details.record_clone_error(err, Utc::now()),
+    }
+}
+
+fn clone(
+    vcs: Vcs,
+    strategy: CloneStrategy,
+    url: &str,
+    path: &str,
+    repository: &str,
+    args: &Cli,
+) -> Result<(), GitError> {
+    log::info!(
+        "{} clone {} {} strategy: {}",
+        vcs.as_str(),
+        url,
+        path,
+        strategy.as_str()
+    );
+
+    if let Err(err) = vcs.clone_repo(
+        url,
+        Path::new(path),
+        strategy.git_options(),
+        args.clone_timeout,
+        args.max_size(),
+    ) {
+        if matches!(err, GitError::TooLarge | GitError::Timeout) && Path::new(path).exists() {
+            if let Err(err) = fs::remove_dir_all(path) {
+                log::error!("Could not remove {path} error: {err}");
+            }
+        }
+        return Err(err);
+    }
+
+    if matches!(strategy, CloneStrategy::Sparse) {
+        git_sparse_checkout_add(Path::new(path), repository, args.clone_timeout)?;
+    }
+    Ok(())
+}
+
+/// Add the folder of the crate and the `SPARSE_FOLDERS` to the sparse checkout in `path`.
+fn git_sparse_checkout_add(path: &Path, repository: &str, timeout: u64) -> Result<(), GitError> {
+    let mut command = git_command();
+    command
+        .current_dir(path)
+        .arg("sparse-checkout")
+        .arg("add")
+        .args(SPARSE_FOLDERS);
+    if let Some(subdir) = get_crate_subdir(repository) {
+        command.arg(subdir);
+    }
+    log::info!("{command:?}");
+    run_command(command, path, timeout, 0)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[expect(
+    clippy::redundant_test_prefix,
+    reason = "named like the tests of the library"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_config() {
+        let root = env::temp_dir().join(format!("rust-digger-{}-clone-config", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        assert_eq!(
+            load_config(&root.join(CONFIG_FILE)).unwrap(),
+            CloneConfig::default()
+        );
+
+        let path = root.join(CONFIG_FILE);
+        fs::write(&path, "[clone]\nstrategy = \"sparse\"\nmax_size = 500\n").unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!(config.strategy, Some(CloneStrategy::Sparse));
+        assert_eq!(config.max_size, Some(500));
+
+        let mut args = Cli::parse_from(["rust-digger-clone", "--strategy", "shallow"]);
+        args.apply_config(&config);
+        assert_eq!(args.strategy(), CloneStrategy::Shallow);
+        assert_eq!(args.max_size(), 500);
+
+        fs::write(&path, "[clone]\nstrategy = \"deep\"\n").unwrap();
+        load_config(&path).unwrap_err();
     }
 }
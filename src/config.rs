@@ -0,0 +1,433 @@
+use std::fs;
+use std::fs::File;
+use std::io::Write as _;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use toml::{Table, Value};
+
+/// A setting in a configuration file of a crate. Each line of the settings file of a repository is one setting.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Setting {
+    /// The file the setting comes from, relative to the root of the repository, e.g. `rustfmt.toml`.
+    pub file: String,
+    /// The keys of nested tables are joined by a dot, e.g. `ignore.files`.
+    pub key: String,
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// `rustfmt`, `clippy` or `lints`.
+    pub tool: String,
+    pub value: Value,
+}
+
+impl Setting {
+    /// Strings without the quotes, everything else in TOML syntax, e.g. `["a", "b"]`.
+    pub fn display_value(&self) -> String {
+        self.value
+            .as_str()
+            .map_or_else(|| self.value.to_string(), str::to_owned)
+    }
+
+    /// The value is not an array or a table.
+    pub const fn is_scalar(&self) -> bool {
+        !matches!(self.value, Value::Array(_) | Value::Table(_))
+    }
+}
+
+/// The tools configured by a file in the root of the repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigTool {
+    CargoConfig,
+    Clippy,
+    Deny,
+    Toolchain,
+}
+
+impl ConfigTool {
+    pub const ALL: [Self; 4] = [Self::Toolchain, Self::Clippy, Self::Deny, Self::CargoConfig];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Toolchain => "toolchain",
+            Self::Clippy => "clippy",
+            Self::Deny => "deny",
+            Self::CargoConfig => "cargo-config",
+        }
+    }
+
+    /// The configuration files of the tool in the order the tool looks for them.
+    pub const fn files(self) -> &'static [&'static str] {
+        match self {
+            Self::Toolchain => &["rust-toolchain", "rust-toolchain.toml"],
+            Self::Clippy => &[".clippy.toml", "clippy.toml"],
+            Self::Deny => &["deny.toml"],
+            Self::CargoConfig => &[".cargo/config", ".cargo/config.toml"],
+        }
+    }
+}
+
+/// The toolchain pinned by a `rust-toolchain.toml` or a `rust-toolchain` file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Toolchain {
+    /// e.g. `stable`, `nightly-2024-01-01` or `1.75.0`.
+    pub channel: String,
+    pub components: Vec<String>,
+    pub targets: Vec<String>,
+}
+
+impl Toolchain {
+    /// Parse the content of a toolchain file. The legacy `rust-toolchain` file might contain only the name of the channel.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `content` is neither a channel name nor a proper TOML file.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let content = content.trim();
+        if !content.is_empty() && !content.contains(['[', '=', '\n']) {
+            return Ok(Self {
+                channel: content.to_owned(),
+                ..Self::default()
+            });
+        }
+
+        let table = content.parse::<Table>().map_err(|err| err.to_string())?;
+        let toolchain = table.get("toolchain");
+        let strings = |name: &str| -> Vec<String> {
+            toolchain
+                .and_then(|toolchain| toolchain.get(name))
+                .and_then(Value::as_array)
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        Ok(Self {
+            channel: toolchain
+                .and_then(|toolchain| toolchain.get("channel"))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned(),
+            components: strings("components"),
+            targets: strings("targets"),
+        })
+    }
+}
+
+/// The configuration files and what they set.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The top-level tables and keys of the `.cargo/config.toml` file, e.g. `build` and `alias`.
+    pub cargo_config: Vec<String>,
+    /// The options set in the `clippy.toml` file, e.g. `msrv`.
+    pub clippy: Vec<String>,
+    /// The sections of the `deny.toml` file, e.g. `licenses` and `bans`.
+    pub deny: Vec<String>,
+    /// The configuration files found in the root of the repository, e.g. `clippy.toml`.
+    pub files: Vec<String>,
+    pub toolchain: Toolchain,
+}
+
+impl Config {
+    fn add(&mut self, tool: ConfigTool, content: &str) -> Result<(), String> {
+        let keys = || -> Result<Vec<String>, String> {
+            let table = content.parse::<Table>().map_err(|err| err.to_string())?;
+            Ok(table.keys().cloned().collect())
+        };
+        match tool {
+            ConfigTool::Toolchain => self.toolchain = Toolchain::parse(content)?,
+            ConfigTool::Clippy => self.clippy = keys()?,
+            ConfigTool::Deny => self.deny = keys()?,
+            ConfigTool::CargoConfig => self.cargo_config = keys()?,
+        }
+        Ok(())
+    }
+
+    /// Find and parse the configuration files among the `files` of the repository in `root`.
+    pub fn from_files(root: &Path, files: &[String]) -> Self {
+        let mut config = Self::default();
+        for tool in ConfigTool::ALL {
+            // only the first file is used by the tool
+            let Some(&file) = tool
+                .files()
+                .iter()
+                .find(|&&name| files.iter().any(|file| file == name))
+            else {
+                continue;
+            };
+            config.files.push(file.to_owned());
+
+            let path = root.join(file);
+            let result = fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| config.add(tool, &content));
+            if let Err(err) = result {
+                log::warn!("Could not parse {}: {err}", path.display());
+            }
+        }
+        config
+    }
+}
+
+/// The settings of the `tool` in the `content` of the TOML `file` of the crate `krate`.
+///
+/// # Errors
+///
+/// Will return `Err` if `content` is not a proper TOML file.
+pub fn parse_settings(
+    tool: &str,
+    krate: &str,
+    file: &str,
+    content: &str,
+) -> Result<Vec<Setting>, String> {
+    let table = content.parse::<Table>().map_err(|err| err.to_string())?;
+    let mut values = vec![];
+    flatten_table(&table, "", &mut values);
+    Ok(values
+        .into_iter()
+        .map(|(key, value)| Setting {
+            tool: tool.to_owned(),
+            krate: krate.to_owned(),
+            file: file.to_owned(),
+            key,
+            value,
+        })
+        .collect())
+}
+
+fn flatten_table(table: &Table, prefix: &str, values: &mut Vec<(String, Value)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value.as_table() {
+            Some(nested) => flatten_table(nested, &key, values),
+            None => values.push((key, value.clone())),
+        }
+    }
+}
+
+/// Save the `settings` in `path` as JSON lines.
+///
+/// # Errors
+///
+/// Will return `Err` if the file cannot be written.
+pub fn save_settings(path: &Path, settings: &[Setting]) -> Result<(), String> {
+    let mut file = File::create(path).map_err(|err| err.to_string())?;
+    for setting in settings {
+        let line = serde_json::to_string(setting).map_err(|err| err.to_string())?;
+        writeln!(&mut file, "{line}").map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+/// Load the settings saved by `save_settings`. The lines that do not match the schema are skipped.
+///
+/// # Errors
+///
+/// Will return `Err` if the file cannot be read.
+pub fn load_settings(path: &Path) -> Result<Vec<Setting>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| log::error!("Invalid setting '{line}' in {}: {err}", path.display()))
+                .ok()
+        })
+        .collect())
+}
+
+/// The lints in the crate-level `#![warn(...)]`, `#![deny(...)]` and `#![forbid(...)]` attributes of `source`.
+///
+/// e.g. `forbid(unsafe_code)` and `deny(clippy::pedantic)`. Attributes inside `cfg_attr` are not included.
+pub fn lint_attributes(source: &str) -> Vec<String> {
+    static RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"#!\[\s*(warn|deny|forbid)\s*\(([^\]\)]*)\)\s*\]").unwrap());
+
+    let code = source
+        .lines()
+        .map(|line| line.split_once("//").map_or(line, |parts| parts.0))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut attributes = vec![];
+    for captures in RE.captures_iter(&code) {
+        for lint in captures[2].split(',').map(str::trim) {
+            let attribute = format!("{}({lint})", &captures[1]);
+            if !lint.is_empty() && !attributes.contains(&attribute) {
+                attributes.push(attribute);
+            }
+        }
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toolchain_parse() {
+        assert_eq!(
+            Toolchain::parse(
+                r#"
+[toolchain]
+channel = "nightly-2024-01-01"
+components = ["rustfmt", "clippy"]
+targets = ["wasm32-unknown-unknown"]
+"#
+            ),
+            Ok(Toolchain {
+                channel: "nightly-2024-01-01".to_owned(),
+                components: vec!["rustfmt".to_owned(), "clippy".to_owned()],
+                targets: vec!["wasm32-unknown-unknown".to_owned()],
+            })
+        );
+        assert_eq!(
+            Toolchain::parse("1.75.0\n").unwrap().channel,
+            "1.75.0".to_owned()
+        );
+        Toolchain::parse("[toolchain\n").unwrap_err();
+    }
+
+    #[test]
+    fn test_settings() {
+        let settings = parse_settings(
+            "rustfmt",
+            "foo",
+            "rustfmt.toml",
+            r#"
+max_width = 100
+imports_granularity = "Crate"
+ignore = ["src/generated.rs", "benches"]
+wrap_comments = true
+
+[unstable]
+format_strings = false
+"#,
+        )
+        .unwrap();
+        let keys = settings
+            .iter()
+            .map(|setting| (setting.key.as_str(), setting.display_value()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            vec![
+                ("ignore", r#"["src/generated.rs", "benches"]"#.to_owned()),
+                ("imports_granularity", "Crate".to_owned()),
+                ("max_width", "100".to_owned()),
+                ("unstable.format_strings", "false".to_owned()),
+                ("wrap_comments", "true".to_owned()),
+            ]
+        );
+        assert_eq!(settings[2].value, Value::Integer(100));
+        assert!(!settings[0].is_scalar());
+        assert!(settings[2].is_scalar());
+        parse_settings("rustfmt", "foo", "rustfmt.toml", "max_width = ").unwrap_err();
+
+        let path = crate::temp_folder("settings").join("rustfmt.jsonl");
+        save_settings(&path, &settings).unwrap();
+        assert_eq!(load_settings(&path), Ok(settings));
+        load_settings(&path.with_extension("txt")).unwrap_err();
+    }
+
+    #[test]
+    fn test_lint_attributes() {
+        let source = "
+//! A crate
+#![forbid(unsafe_code)]
+#![deny(
+    missing_docs, // the public API
+    clippy::pedantic,
+)]
+#![allow(dead_code)]
+#![cfg_attr(not(test), warn(clippy::unwrap_used))]
+#![doc(html_root_url = \"https://docs.rs/foo\")]
+#![warn(rust_2018_idioms)]
+#![forbid(unsafe_code)]
+";
+        assert_eq!(
+            lint_attributes(source),
+            vec![
+                "forbid(unsafe_code)",
+                "deny(missing_docs)",
+                "deny(clippy::pedantic)",
+                "warn(rust_2018_idioms)",
+            ]
+        );
+        assert_eq!(lint_attributes("fn main() {}"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_from_files() {
+        let root = crate::temp_folder("config");
+        fs::create_dir_all(root.join(".cargo")).unwrap();
+        fs::write(root.join("rust-toolchain"), "stable\n").unwrap();
+        fs::write(
+            root.join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"nightly\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("clippy.toml"),
+            "msrv = \"1.70\"\ntoo-many-arguments-threshold = 10\n",
+        )
+        .unwrap();
+        fs::write(root.join(".clippy.toml"), "msrv = \"1.60\"\n").unwrap();
+        fs::write(
+            root.join("deny.toml"),
+            "[licenses]\nallow = [\"MIT\"]\n\n[bans]\n",
+        )
+        .unwrap();
+        fs::write(root.join(".cargo/config"), "[build]\n").unwrap();
+        fs::write(root.join(".cargo/config.toml"), "[alias\n").unwrap();
+
+        // the tools prefer the older names
+        let files = [
+            "rust-toolchain",
+            "rust-toolchain.toml",
+            "clippy.toml",
+            ".clippy.toml",
+            "deny.toml",
+            ".cargo/config",
+            ".cargo/config.toml",
+            "src/lib.rs",
+        ]
+        .iter()
+        .map(|&file| file.to_owned())
+        .collect::<Vec<_>>();
+        let config = Config::from_files(&root, &files);
+        assert_eq!(
+            config.files,
+            vec![
+                "rust-toolchain",
+                ".clippy.toml",
+                "deny.toml",
+                ".cargo/config"
+            ]
+        );
+        assert_eq!(config.toolchain.channel, "stable");
+        assert_eq!(config.clippy, vec!["msrv"]);
+        assert_eq!(config.deny, vec!["bans", "licenses"]);
+        assert_eq!(config.cargo_config, vec!["build"]);
+
+        let files = ["clippy.toml", ".cargo/config.toml"].map(str::to_owned);
+        let config = Config::from_files(&root, &files);
+        assert_eq!(config.files, vec!["clippy.toml", ".cargo/config.toml"]);
+        assert_eq!(config.clippy, vec!["msrv", "too-many-arguments-threshold"]);
+        assert_eq!(config.cargo_config, Vec::<String>::new());
+
+        assert_eq!(Config::from_files(&root, &[]), Config::default());
+    }
+}
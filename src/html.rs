@@ -12,8 +12,9 @@ pub type Partials = liquid::partials::EagerCompiler<liquid::partials::InMemorySo
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const PAGE_SIZE: usize = 100;
 
+use rust_digger::ci::{latest_official_actions, ActionWarning};
 use rust_digger::{
-    collected_data_root, load_details, read_crates, Crate, CratesByOwner, Owners, Repo, User,
+    load_details, load_package_details, read_crates, Crate, CratesByOwner, Owners, Repo, User,
 };
 mod read;
 use read::{read_crate_owners, read_teams, read_users};
@@ -54,6 +55,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     add_owners_to_crates(&mut crates, &users, &owner_by_crate_id);
     load_details_for_all_the_crates(&mut crates);
+    mark_outdated_actions(&mut crates);
     create_folders();
 
     std::thread::scope(|scope| {
@@ -109,6 +111,38 @@ fn main() -> Result<(), Box<dyn Error>> {
 fn load_details_for_all_the_crates(crates: &mut [Crate]) {
     for krate in crates.iter_mut() {
         krate.details = load_details(&krate.repository);
+        krate.package_details = load_package_details(&krate.name);
+        // fall back to the analysis of the published package if we could not analyze the repository
+        if krate.details.analysed_at.is_empty() && !krate.package_details.analysed_at.is_empty() {
+            krate.details = krate.package_details.clone();
+        }
+    }
+}
+
+/// Add the `Outdated` warnings to the actions of the crates.
+///
+/// We compare the actions with the newest versions used by any of the crates,
+/// so we can only do it once we have the details of all of them.
+fn mark_outdated_actions(crates: &mut [Crate]) {
+    let latest = latest_official_actions(crates.iter().flat_map(|krate| &krate.details.ci_actions));
+    log::info!("latest official actions: {latest:?}");
+    let outdated = ActionWarning::Outdated.as_str();
+    for krate in crates.iter_mut() {
+        let details = &mut krate.details;
+        for action in &details.ci_actions {
+            let warnings = details
+                .ci_action_warnings
+                .entry(action.clone())
+                .or_default();
+            // older versions of rust-digger-vcs used a fixed list of the latest versions
+            warnings.retain(|warning| warning != outdated);
+            if ActionWarning::is_outdated(action, &latest) {
+                warnings.push(outdated.to_owned());
+            }
+        }
+        details
+            .ci_action_warnings
+            .retain(|_action, warnings| !warnings.is_empty());
     }
 }
 
@@ -1,17 +1,70 @@
-use std::collections::HashMap;
+// inside `mod tests` the names without the prefix would shadow the functions they test
+#![cfg_attr(
+    test,
+    expect(
+        clippy::redundant_test_prefix,
+        reason = "the tests are named after the functions they test"
+    )
+)]
+
+pub mod bots;
+pub mod ci;
+pub mod config;
+pub mod license;
+pub mod manifest;
+pub mod package;
+pub mod readme;
+pub mod vcs_backend;
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fs;
 use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{Read, Write as _};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt as _;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Instant;
 
+use core::cmp::Reverse;
+use core::hash::BuildHasher;
+
+use chrono::{DateTime, Duration, Utc};
 use once_cell::sync::Lazy;
 use regex::Regex;
 
+use manifest::ManifestReadme;
+
+/// The first retry of a failed clone happens after this many hours, every further failure doubles it.
+const CLONE_RETRY_BASE_HOURS: i64 = 24;
+/// Stop doubling the waiting time after this many failures (24 hours * 2^6 = 64 days).
+const CLONE_RETRY_MAX_EXPONENT: u32 = 6;
+
+/// How often we measure the folder of a running command. Walking a large clone takes a while.
+const SIZE_CHECK_INTERVAL: core::time::Duration = core::time::Duration::from_secs(5);
+
+/// The pages of a repository in cgit, e.g. `https://git.kernel.org/pub/scm/git/git.git/tree/`.
+const CGIT_PAGES: &[&str] = &[
+    "about", "summary", "refs", "log", "tree", "commit", "diff", "stats", "plain",
+];
+
+/// The version of the data collected by `rust-digger-vcs`.
+///
+/// Increase it when the analysis collects new fields or fixes the old ones,
+/// so the next run analyzes the repositories again even if their HEAD has not changed.
+pub const ANALYSIS_VERSION: u32 = 7;
+
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 #[allow(clippy::struct_excessive_bools)]
+#[expect(
+    clippy::arbitrary_source_item_ordering,
+    reason = "the fields are grouped by topic and serialized in this order"
+)]
 pub struct Details {
-    pub has_github_action: bool,
-    pub has_gitlab_pipeline: bool,
     pub commit_count: i32,
     pub cargo_toml_in_root: bool,
     pub cargo_fmt: String,
@@ -19,6 +72,65 @@ pub struct Details {
     #[serde(default = "empty_string")]
     pub git_clone_error: String,
 
+    #[serde(default = "empty_string")]
+    pub git_clone_error_at: String,
+
+    #[serde(default = "get_zero_u32")]
+    pub git_clone_attempts: u32,
+
+    #[serde(default = "empty_string")]
+    pub clone_strategy: String,
+
+    /// The failure of the last git command of the vcs stage, e.g. `timeout`.
+    #[serde(default = "empty_string")]
+    pub vcs_error: String,
+
+    #[serde(default = "empty_string")]
+    pub vcs_error_at: String,
+
+    #[serde(default = "empty_string")]
+    pub vcs: String,
+
+    #[serde(default = "empty_string")]
+    pub remote_head: String,
+
+    #[serde(default = "empty_string")]
+    pub analysed_head: String,
+
+    #[serde(default = "empty_string")]
+    pub analysed_at: String,
+
+    /// The `ANALYSIS_VERSION` of the last analysis.
+    #[serde(default = "get_zero_u32")]
+    pub analysis_version: u32,
+
+    #[serde(default = "empty_string")]
+    pub redirected_url: String,
+
+    #[serde(default = "empty_string")]
+    pub repo_state: String,
+
+    #[serde(default = "empty_string")]
+    pub source: String,
+
+    #[serde(default = "empty_string")]
+    pub package_version: String,
+
+    #[serde(default = "empty_string")]
+    pub package_compared_head: String,
+
+    #[serde(default = "empty_vector")]
+    pub package_only_files: Vec<String>,
+
+    #[serde(default = "empty_string")]
+    pub package_version_tag: String,
+
+    #[serde(default = "empty_string")]
+    pub package_vcs_commit: String,
+
+    #[serde(default = "default_false")]
+    pub package_vcs_commit_in_repo: bool,
+
     #[serde(default = "default_false")]
     pub has_rustfmt_toml: bool,
 
@@ -30,13 +142,226 @@ pub struct Details {
 
     #[serde(default = "empty_string")]
     pub rust_version: String,
+
+    #[serde(default = "empty_string")]
+    pub manifest_name: String,
+
+    #[serde(default = "empty_string")]
+    pub license: String,
+
+    #[serde(default = "empty_string")]
+    pub license_file: String,
+
+    #[serde(default = "empty_string")]
+    pub description: String,
+
+    #[serde(default = "empty_vector")]
+    pub categories: Vec<String>,
+
+    #[serde(default = "empty_vector")]
+    pub keywords: Vec<String>,
+
+    #[serde(default)]
+    pub readme_field: ManifestReadme,
+
+    #[serde(default = "default_true")]
+    pub publish: bool,
+
+    #[serde(default = "empty_vector")]
+    pub features: Vec<String>,
+
+    #[serde(default = "empty_vector")]
+    pub targets: Vec<String>,
+
+    #[serde(default = "default_false")]
+    pub build_script: bool,
+
+    #[serde(default = "empty_string")]
+    pub links: String,
+
+    #[serde(default = "empty_vector")]
+    pub badges: Vec<String>,
+
+    /// The differences between Crates.io and the `Cargo.toml` in the repository for each crate in the repository.
+    #[serde(default = "empty_map")]
+    pub manifest_mismatches: BTreeMap<String, Vec<String>>,
+
+    /// The `Cargo.toml` in the root of the repository has a `[workspace]` table.
+    #[serde(default = "default_false")]
+    pub workspace: bool,
+
+    #[serde(default = "default_false")]
+    pub workspace_virtual: bool,
+
+    #[serde(default = "empty_vector")]
+    pub workspace_members: Vec<String>,
+
+    /// The published crates that are members of the workspace.
+    #[serde(default = "empty_vector")]
+    pub workspace_crates: Vec<String>,
+
+    #[serde(default = "default_false")]
+    pub workspace_lints: bool,
+
+    #[serde(default = "get_zero_u32")]
+    pub workspace_dependencies: u32,
+
+    /// The CI systems configured in the repository. (See `ci::CiProvider`.)
+    #[serde(default = "empty_vector")]
+    pub ci: Vec<String>,
+
+    /// What the GitHub Actions workflows run. (See `ci::CiCheck`.)
+    #[serde(default = "empty_vector")]
+    pub ci_checks: Vec<String>,
+
+    /// The operating systems of the GitHub Actions runners.
+    #[serde(default = "empty_vector")]
+    pub ci_os: Vec<String>,
+
+    /// The cron schedules of the GitHub Actions workflows.
+    #[serde(default = "empty_vector")]
+    pub ci_cron: Vec<String>,
+
+    /// The `uses` fields of the GitHub Actions workflows, e.g. `actions/checkout@v4`.
+    #[serde(default = "empty_vector")]
+    pub ci_actions: Vec<String>,
+
+    /// The problems with each of the `ci_actions`. (See `ci::ActionWarning`.)
+    #[serde(default = "empty_map")]
+    pub ci_action_warnings: BTreeMap<String, Vec<String>>,
+
+    /// The dependency update bots configured in the repository. (See `bots::UpdateBot`.)
+    #[serde(default = "empty_vector")]
+    pub update_bots: Vec<String>,
+
+    /// The ecosystems the bots update, e.g. `cargo` and `github-actions`.
+    #[serde(default = "empty_vector")]
+    pub update_ecosystems: Vec<String>,
+
+    /// How often the bots update the cargo dependencies.
+    #[serde(default = "empty_string")]
+    pub update_interval: String,
+
+    /// The configuration files of the tools in the root of the repository. (See `config::ConfigTool`.)
+    #[serde(default = "empty_vector")]
+    pub config_files: Vec<String>,
+
+    /// The channel in the `rust-toolchain.toml` or `rust-toolchain` file.
+    #[serde(default = "empty_string")]
+    pub toolchain_channel: String,
+
+    #[serde(default = "empty_vector")]
+    pub toolchain_components: Vec<String>,
+
+    #[serde(default = "empty_vector")]
+    pub toolchain_targets: Vec<String>,
+
+    /// The options set in `clippy.toml` or `.clippy.toml`.
+    #[serde(default = "empty_vector")]
+    pub clippy_config: Vec<String>,
+
+    /// The sections of `deny.toml`.
+    #[serde(default = "empty_vector")]
+    pub deny_config: Vec<String>,
+
+    /// The top-level tables and keys of `.cargo/config.toml`.
+    #[serde(default = "empty_vector")]
+    pub cargo_config: Vec<String>,
+
+    /// The tools in the `[lints]` table of the `Cargo.toml`.
+    #[serde(default = "empty_vector")]
+    pub lints: Vec<String>,
+
+    /// The crate-level lint attributes in `src/lib.rs`, e.g. `forbid(unsafe_code)`.
+    #[serde(default = "empty_vector")]
+    pub lint_attributes: Vec<String>,
+
+    /// The LICENSE and COPYING files of the crate.
+    #[serde(default = "empty_vector")]
+    pub license_files: Vec<String>,
+
+    /// The SPDX identifiers of the licenses we recognized in the `license_files`.
+    #[serde(default = "empty_vector")]
+    pub licenses_in_files: Vec<String>,
+
+    /// The license of the latest release on Crates.io.
+    #[serde(default = "empty_string")]
+    pub published_license: String,
+
+    /// The problems with the license files. (See `license::LicenseProblem`.)
+    #[serde(default = "empty_vector")]
+    pub license_problems: Vec<String>,
+
+    /// The README file of the crate relative to the root of the repository. Empty if there is none.
+    #[serde(default = "empty_string")]
+    pub readme_file: String,
+
+    #[serde(default = "empty_string")]
+    pub readme_format: String,
+
+    /// The size of the README file in bytes.
+    #[serde(default = "get_zero_u32")]
+    pub readme_size: u32,
+
+    #[serde(default = "empty_vector")]
+    pub readme_headings: Vec<String>,
+
+    #[serde(default = "default_false")]
+    pub readme_has_install: bool,
+
+    #[serde(default = "default_false")]
+    pub readme_has_usage: bool,
+
+    #[serde(default = "default_false")]
+    pub readme_has_code_example: bool,
+
+    /// The kinds of badges in the README. (See `readme::Badge`.)
+    #[serde(default = "empty_vector")]
+    pub readme_badges: Vec<String>,
+
+    /// The file in the `readme` field of the `Cargo.toml` does not exist.
+    #[serde(default = "default_false")]
+    pub readme_missing: bool,
 }
 
 impl Details {
+    pub fn clear_clone_error(&mut self) {
+        self.git_clone_error.clear();
+        self.git_clone_error_at.clear();
+        self.git_clone_attempts = 0;
+    }
+
+    pub fn clear_vcs_error(&mut self) {
+        self.vcs_error.clear();
+        self.vcs_error_at.clear();
+    }
+
+    /// After a failed clone or pull we wait 1, 2, 4, ... days (up to 64) before trying again.
+    pub fn clone_retry_due(&self, now: DateTime<Utc>) -> bool {
+        if self.git_clone_error.is_empty() {
+            return true;
+        }
+
+        let failed_at = match DateTime::parse_from_rfc3339(&self.git_clone_error_at) {
+            Ok(failed_at) => failed_at.with_timezone(&Utc),
+            Err(_) => return true,
+        };
+
+        let exponent = self
+            .git_clone_attempts
+            .saturating_sub(1)
+            .min(CLONE_RETRY_MAX_EXPONENT);
+        let wait = Duration::try_hours(CLONE_RETRY_BASE_HOURS << exponent).unwrap();
+        failed_at + wait <= now
+    }
+
+    /// Was `head` (a commit or a package version) already analyzed by the current version of the analysis?
+    pub fn is_analysed(&self, head: &str) -> bool {
+        !head.is_empty() && head == self.analysed_head && self.analysis_version == ANALYSIS_VERSION
+    }
+
     pub fn new() -> Self {
         Self {
-            has_github_action: false,
-            has_gitlab_pipeline: false,
             commit_count: 0,
             cargo_toml_in_root: false,
             cargo_fmt: String::new(),
@@ -44,10 +369,92 @@ impl Details {
             has_dot_rustfmt_toml: false,
 
             git_clone_error: String::new(),
+            git_clone_error_at: String::new(),
+            git_clone_attempts: 0,
+            clone_strategy: String::new(),
+            vcs_error: String::new(),
+            vcs_error_at: String::new(),
+            vcs: String::new(),
+            remote_head: String::new(),
+            analysed_head: String::new(),
+            analysed_at: String::new(),
+            analysis_version: 0,
+            redirected_url: String::new(),
+            repo_state: String::new(),
+            source: String::new(),
+            package_version: String::new(),
+            package_compared_head: String::new(),
+            package_only_files: vec![],
+            package_version_tag: String::new(),
+            package_vcs_commit: String::new(),
+            package_vcs_commit_in_repo: false,
             edition: String::new(),
             rust_version: String::new(),
+            manifest_name: String::new(),
+            license: String::new(),
+            license_file: String::new(),
+            description: String::new(),
+            categories: vec![],
+            keywords: vec![],
+            readme_field: ManifestReadme::Unset,
+            publish: true,
+            features: vec![],
+            targets: vec![],
+            build_script: false,
+            links: String::new(),
+            badges: vec![],
+            manifest_mismatches: BTreeMap::new(),
+            workspace: false,
+            workspace_virtual: false,
+            workspace_members: vec![],
+            workspace_crates: vec![],
+            workspace_lints: false,
+            workspace_dependencies: 0,
+            ci: vec![],
+            ci_checks: vec![],
+            ci_os: vec![],
+            ci_cron: vec![],
+            ci_actions: vec![],
+            ci_action_warnings: BTreeMap::new(),
+            update_bots: vec![],
+            update_ecosystems: vec![],
+            update_interval: String::new(),
+            config_files: vec![],
+            toolchain_channel: String::new(),
+            toolchain_components: vec![],
+            toolchain_targets: vec![],
+            clippy_config: vec![],
+            deny_config: vec![],
+            cargo_config: vec![],
+            lints: vec![],
+            lint_attributes: vec![],
+            license_files: vec![],
+            licenses_in_files: vec![],
+            published_license: String::new(),
+            license_problems: vec![],
+            readme_file: String::new(),
+            readme_format: String::new(),
+            readme_size: 0,
+            readme_headings: vec![],
+            readme_has_install: false,
+            readme_has_usage: false,
+            readme_has_code_example: false,
+            readme_badges: vec![],
+            readme_missing: false,
         }
     }
+
+    /// Save the kind of the failure, when it happened and how many times we failed in a row.
+    pub fn record_clone_error(&mut self, error: GitError, now: DateTime<Utc>) {
+        error.as_str().clone_into(&mut self.git_clone_error);
+        self.git_clone_error_at = now.to_rfc3339();
+        self.git_clone_attempts += 1;
+    }
+
+    pub fn record_vcs_error(&mut self, error: GitError, now: DateTime<Utc>) {
+        error.as_str().clone_into(&mut self.vcs_error);
+        self.vcs_error_at = now.to_rfc3339();
+    }
 }
 
 impl Default for Details {
@@ -56,7 +463,132 @@ impl Default for Details {
     }
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// The reasons a `git clone` or `git pull` can fail, as far as we can tell from the output of git.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GitError {
+    AuthRequired,
+    Network,
+    NotFound,
+    Other,
+    Timeout,
+    TooLarge,
+}
+
+impl GitError {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::NotFound => "not-found",
+            Self::AuthRequired => "auth-required",
+            Self::Timeout => "timeout",
+            Self::TooLarge => "too-large",
+            Self::Network => "network",
+            Self::Other => "other",
+        }
+    }
+
+    /// Classify a failure based on the STDERR of git.
+    pub fn from_stderr(stderr: &str) -> Self {
+        let stderr = stderr.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| stderr.contains(needle));
+
+        // a bare "not found" might be about a missing program, e.g. "git: 'remote-hg' ... not found"
+        let repository_not_found = stderr
+            .lines()
+            .any(|line| line.contains("repository '") && line.trim_end().ends_with("' not found"));
+        if repository_not_found
+            || has(&[
+                "repository not found",
+                "does not exist",
+                "does not appear to be a git repository",
+                "http error 404",
+            ])
+        {
+            return Self::NotFound;
+        }
+        if has(&[
+            "authentication failed",
+            "could not read username",
+            "terminal prompts disabled",
+            "permission denied",
+        ]) {
+            return Self::AuthRequired;
+        }
+        if has(&["timed out", "timeout"]) {
+            return Self::Timeout;
+        }
+        if has(&["too large", "exceeds", "no space left"]) {
+            return Self::TooLarge;
+        }
+        if has(&[
+            "could not resolve host",
+            "unable to access",
+            "connection refused",
+            "connection reset",
+            "early eof",
+            "rpc failed",
+        ]) {
+            return Self::Network;
+        }
+        Self::Other
+    }
+}
+
+/// What we found in the default branch of a repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RepoState {
+    /// There are commits, but HEAD points to a branch that does not exist.
+    DefaultBranchMissing,
+    /// There are no commits at all.
+    Empty,
+    /// There are `.rs` files, but no `Cargo.toml` anywhere.
+    NoCargoToml,
+    /// There are no `.rs` files, e.g. there is only a README.
+    NoRustFiles,
+    Ok,
+}
+
+impl RepoState {
+    pub const ALL: [Self; 5] = [
+        Self::Ok,
+        Self::Empty,
+        Self::DefaultBranchMissing,
+        Self::NoRustFiles,
+        Self::NoCargoToml,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Empty => "empty",
+            Self::DefaultBranchMissing => "default-branch-missing",
+            Self::NoRustFiles => "no-rust-files",
+            Self::NoCargoToml => "no-cargo-toml",
+        }
+    }
+
+    /// Classify a checked out commit based on the list of files in it.
+    pub fn from_files(files: &[String]) -> Self {
+        let is_rust_file = |file: &String| {
+            Path::new(file)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("rs"))
+        };
+        if !files.iter().any(is_rust_file) {
+            return Self::NoRustFiles;
+        }
+        if !files
+            .iter()
+            .any(|file| file == "Cargo.toml" || file.ends_with("/Cargo.toml"))
+        {
+            return Self::NoCargoToml;
+        }
+        Self::Ok
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[non_exhaustive]
 pub enum RepoPlatform {
     GitHub,    // https://github.com/
@@ -88,6 +620,13 @@ pub struct Repo {
     pub bold: bool,
 }
 
+pub fn get_repo_types() -> Vec<Repo> {
+    let text = include_str!("../repo_types.yaml");
+
+    let repos: Vec<Repo> = serde_yaml::from_str(text).unwrap();
+    repos
+}
+
 const fn get_default_bold() -> bool {
     false
 }
@@ -125,6 +664,21 @@ pub struct Crate {
 
     #[serde(default = "empty_details")]
     pub details: Details,
+
+    /// The analysis of the published package, if we have one.
+    #[serde(default = "empty_details")]
+    pub package_details: Details,
+}
+
+/// A row of `versions.csv`. (We only list the columns we use.)
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct Version {
+    pub crate_id: String,
+    pub created_at: String,
+    pub num: String,
+    pub yanked: String,
+    #[serde(default = "empty_string")]
+    pub license: String,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -147,14 +701,30 @@ const fn empty_string() -> String {
     String::new()
 }
 
+const fn empty_vector() -> Vec<String> {
+    Vec::new()
+}
+
+const fn empty_map() -> BTreeMap<String, Vec<String>> {
+    BTreeMap::new()
+}
+
 const fn get_zero() -> usize {
     0
 }
 
+const fn get_zero_u32() -> u32 {
+    0
+}
+
 const fn default_false() -> bool {
     false
 }
 
+const fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Team {
     pub avatar: String,
@@ -194,6 +764,7 @@ impl Crate {
             owner_name: String::new(),
 
             details: Details::new(),
+            package_details: Details::new(),
         }
     }
 }
@@ -203,6 +774,15 @@ impl Default for Crate {
     }
 }
 
+/// A repository URL split into the `name` of the forge in `repo_types.yaml`, the owner, the repo and the clone URL.
+struct RepositoryUrl {
+    clone_url: String,
+    host: String,
+    owner: String,
+    platform: Option<RepoPlatform>,
+    repo: String,
+}
+
 //type RepoPercentage<'a> = HashMap<&'a str, String>;
 pub type Owners = HashMap<String, String>;
 pub type CratesByOwner = HashMap<String, Vec<String>>;
@@ -212,19 +792,119 @@ pub fn get_repos_folder() -> PathBuf {
     PathBuf::from("repos")
 }
 
+/// Split the repository URL based on the forge with the longest matching `url` in `repo_types.yaml`.
+///
+/// The owner and the repo are returned as they appear in the URL.
+fn match_repository(repository: &str) -> Option<RepositoryUrl> {
+    // the bold entries mark badly formatted URLs, e.g. the ones with http://
+    static FORGES: Lazy<Vec<Repo>> = Lazy::new(|| {
+        get_repo_types()
+            .into_iter()
+            .filter(|forge| !forge.bold)
+            .map(|mut forge| {
+                if !forge.url.ends_with('/') {
+                    forge.url.push('/');
+                }
+                forge
+            })
+            .collect()
+    });
+
+    let forge = FORGES
+        .iter()
+        .filter(|forge| {
+            repository
+                .get(..forge.url.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(&forge.url))
+        })
+        .min_by_key(|forge| Reverse(forge.url.len()))?;
+    let segments = repository[forge.url.len()..]
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+
+    let (owner, repo, clone_url) = match (forge.name.as_str(), &forge.platform) {
+        ("chiselapp", _) => match *segments.as_slice() {
+            ["user", owner, "repository", repo, ..] => (
+                owner,
+                repo,
+                format!("{}user/{owner}/repository/{repo}", forge.url),
+            ),
+            _ => return None,
+        },
+        // https://sr.ht/~owner/project is the page of a project, the repository of the same name is on git.sr.ht
+        ("sourcehut", _) => {
+            let (owner, repo) = (segments.first()?, segments.get(1)?);
+            (*owner, *repo, format!("https://git.sr.ht/{owner}/{repo}"))
+        }
+        ("sourceforge", _) => match *segments.as_slice() {
+            ["projects" | "p", project, ..] => (
+                project,
+                project,
+                format!("https://git.code.sf.net/p/{project}/code"),
+            ),
+            _ => return None,
+        },
+        // the path of a repository in cgit can have any number of parts
+        (_, &Some(RepoPlatform::Cgit)) => {
+            let path = segments
+                .iter()
+                .take_while(|segment| !CGIT_PAGES.contains(segment))
+                .copied()
+                .collect::<Vec<_>>();
+            let repo = path.last()?;
+            let owner = path
+                .len()
+                .checked_sub(2)
+                .and_then(|index| path.get(index))
+                .map_or(forge.name.as_str(), |owner| owner);
+            (owner, *repo, format!("{}{}", forge.url, path.join("/")))
+        }
+        _ => {
+            let (owner, repo) = (segments.first()?, segments.get(1)?);
+            (*owner, *repo, format!("{}{owner}/{repo}", forge.url))
+        }
+    };
+    Some(RepositoryUrl {
+        host: forge.name.clone(),
+        owner: owner.to_owned(),
+        repo: repo.to_owned(),
+        platform: forge.platform,
+        clone_url,
+    })
+}
+
 pub fn get_owner_and_repo(repository: &str) -> (String, String, String) {
-    static RE: Lazy<Regex> =
-        Lazy::new(|| Regex::new("^https://(github|gitlab).com/([^/]+)/([^/]+)/?.*$").unwrap());
-    let repo_url = if let Some(value) = RE.captures(repository) {
-        value
-    } else {
+    let Some(url) = match_repository(repository) else {
         log::warn!("No match for repo in '{}'", &repository);
         return (String::new(), String::new(), String::new());
     };
-    let host = repo_url[1].to_lowercase();
-    let owner = repo_url[2].to_lowercase();
-    let repo = repo_url[3].to_lowercase();
-    (host, owner, repo)
+    (
+        url.host.to_lowercase(),
+        url.owner.to_lowercase(),
+        url.repo.to_lowercase(),
+    )
+}
+
+/// The URL we can use to clone the repository. It is usually the same as the URL of the
+/// web page of the repository, but that might point to a folder inside the repository.
+pub fn get_clone_url(repository: &str) -> Option<String> {
+    match_repository(repository).map(|url| url.clone_url)
+}
+
+/// The `platform` of the forge of the repository in `repo_types.yaml`.
+pub fn get_repo_platform(repository: &str) -> Option<RepoPlatform> {
+    match_repository(repository)?.platform
+}
+
+/// The subdirectory of the crate if the repository URL points inside the repository,
+/// e.g. it is `crates/qux` for `https://github.com/foo/bar/tree/main/crates/qux`.
+pub fn get_crate_subdir(repository: &str) -> Option<String> {
+    static RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new("^https://(github|gitlab).com/[^/]+/[^/]+/(-/)?tree/[^/]+/(.+?)/?$").unwrap()
+    });
+    RE.captures(repository)
+        .map(|captures| captures[3].to_owned())
 }
 
 pub fn percentage(num: usize, total: usize) -> String {
@@ -236,10 +916,6 @@ pub fn repo_details_root() -> PathBuf {
     PathBuf::from("repo-details")
 }
 
-pub fn collected_data_root() -> PathBuf {
-    PathBuf::from("collected-data")
-}
-
 pub fn get_details_path(repository: &str) -> Option<PathBuf> {
     let (host, owner, repo) = get_owner_and_repo(repository);
 
@@ -251,6 +927,11 @@ pub fn get_details_path(repository: &str) -> Option<PathBuf> {
     Some(details_path)
 }
 
+/// The settings collected from the configuration files of the repository. (See `config::Setting`.)
+pub fn get_settings_path(repository: &str) -> Option<PathBuf> {
+    get_details_path(repository).map(|path| path.with_extension("settings.jsonl"))
+}
+
 pub fn load_details(repository: &str) -> Details {
     log::info!("Load details started for {}", repository);
 
@@ -259,11 +940,30 @@ pub fn load_details(repository: &str) -> Details {
         None => return Details::new(),
     };
 
+    load_details_from(&details_path)
+}
+
+/// The details of the analysis of the published package of a crate.
+pub fn get_package_details_path(name: &str) -> PathBuf {
+    build_path(repo_details_root(), &["crates-io", name], Some("json"))
+}
+
+/// The settings collected from the configuration files of the published package of a crate.
+pub fn get_package_settings_path(name: &str) -> PathBuf {
+    get_package_details_path(name).with_extension("settings.jsonl")
+}
+
+pub fn load_package_details(name: &str) -> Details {
+    log::info!("Load package details started for {}", name);
+    load_details_from(&get_package_details_path(name))
+}
+
+fn load_details_from(details_path: &Path) -> Details {
     if !details_path.exists() {
         return Details::new();
     }
 
-    match File::open(&details_path) {
+    match File::open(details_path) {
         Ok(file) => {
             match serde_json::from_reader(file) {
                 Ok(details) => return details,
@@ -303,11 +1003,23 @@ pub fn save_details(repository: &str, details: &Details) {
     let _res = fs::create_dir_all(repo_details_root().join(&host).join(&owner));
     let details_path = build_path(repo_details_root(), &[&host, &owner, &repo], Some("json"));
     // log::info!("details {:#?}", &details);
-    log::info!("Going to save in details_path {:?}", &details_path);
     // if Path::new(&details_path).exists() {
     //     match File::open(details_path.to_string()) {
     // }
 
+    save_details_to(&details_path, details);
+}
+
+pub fn save_package_details(name: &str, details: &Details) {
+    log::info!("save_package_details for '{name}'");
+
+    let details_path = get_package_details_path(name);
+    let _res = fs::create_dir_all(repo_details_root().join("crates-io"));
+    save_details_to(&details_path, details);
+}
+
+fn save_details_to(details_path: &Path, details: &Details) {
+    log::info!("Going to save in details_path {:?}", &details_path);
     let content = serde_json::to_string(&details).unwrap();
     let mut file = File::create(details_path).unwrap();
     writeln!(&mut file, "{content}").unwrap();
@@ -347,6 +1059,279 @@ pub fn read_crates(limit: u32) -> Result<Vec<Crate>, String> {
     Ok(crates)
 }
 
+/// A `git` command that never waits for the user (e.g. asking for a username or a password).
+pub fn git_command() -> Command {
+    let mut command = Command::new("git");
+    command
+        .stdin(Stdio::null())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ASKPASS", "")
+        .env("SSH_ASKPASS", "")
+        .env("GCM_INTERACTIVE", "never")
+        .env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes")
+        // we look for some of the messages of git in its STDERR
+        .env("LC_ALL", "C");
+    command
+}
+
+/// Run a git command, return its STDOUT or the classified failure.
+///
+/// The command is killed if it runs longer than `timeout` seconds
+/// or if `path` grows beyond `max_size` MB. 0 means no limit for either of them.
+///
+/// # Errors
+///
+/// Will return `Err` if the command could not be run, was killed or failed.
+pub fn run_command(
+    command: Command,
+    path: &Path,
+    timeout: u64,
+    max_size: u64,
+) -> Result<String, GitError> {
+    run_command_with_stderr(command, path, timeout, max_size).map(|(stdout, _stderr)| stdout)
+}
+
+/// Same as `run_command`, but also return the STDERR of a successful command, e.g. to look for warnings.
+///
+/// # Errors
+///
+/// Will return `Err` if the command could not be run, was killed or failed.
+pub fn run_command_with_stderr(
+    mut command: Command,
+    path: &Path,
+    timeout: u64,
+    max_size: u64,
+) -> Result<(String, String), GitError> {
+    // in its own process group so we can kill the helpers git starts, e.g. git-remote-https
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = match command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            log::error!("Could not run {command:?} error: {err}");
+            return Err(GitError::Other);
+        }
+    };
+
+    // read the output in separate threads so git won't block on a full pipe while we wait for it
+    let stdout = child.stdout.take().map(read_in_thread);
+    let stderr = child.stderr.take().map(read_in_thread);
+
+    let start = Instant::now();
+    let max_bytes = max_size * 1024 * 1024;
+    let mut size_checked = start;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => {}
+            Err(err) => {
+                log::error!("Error waiting for {command:?} error: {err}");
+                return Err(GitError::Other);
+            }
+        }
+        if 0 < timeout && timeout < start.elapsed().as_secs() {
+            log::warn!("{command:?} is running longer than {timeout} sec, aborting");
+            kill_process_group(&mut child);
+            return Err(GitError::Timeout);
+        }
+        if 0 < max_bytes && SIZE_CHECK_INTERVAL <= size_checked.elapsed() {
+            size_checked = Instant::now();
+            if max_bytes < dir_size(path) {
+                log::warn!("{} is larger than {max_size} MB, aborting", path.display());
+                kill_process_group(&mut child);
+                return Err(GitError::TooLarge);
+            }
+        }
+        std::thread::sleep(core::time::Duration::from_millis(100));
+    };
+
+    let stdout = stdout
+        .map(|reader| reader.join().unwrap_or_default())
+        .unwrap_or_default();
+    let stderr = stderr
+        .map(|reader| reader.join().unwrap_or_default())
+        .unwrap_or_default();
+    if !status.success() {
+        log::warn!(
+            "{command:?} exit code: '{}' in folder {:?} stderr: {}",
+            status,
+            env::current_dir().unwrap_or_default(),
+            stderr
+        );
+        return Err(GitError::from_stderr(&stderr));
+    }
+    log::info!("{command:?} exit code: '{}'", status);
+
+    if 0 < max_bytes && max_bytes < dir_size(path) {
+        log::warn!("{} is larger than {max_size} MB", path.display());
+        return Err(GitError::TooLarge);
+    }
+
+    Ok((stdout, stderr))
+}
+
+/// Kill the `child` and the processes it started. They would keep the pipes open and block the threads reading them.
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    match libc::pid_t::try_from(child.id()) {
+        Ok(group) => {
+            // SAFETY: killpg only sends a signal, to the process group we created for the child
+            if unsafe { libc::killpg(group, libc::SIGKILL) } != 0 {
+                log::warn!(
+                    "Could not kill the process group {group}: {}",
+                    std::io::Error::last_os_error()
+                );
+                kill_process(child);
+            }
+        }
+        Err(_) => kill_process(child),
+    }
+    let _res = child.wait();
+}
+
+/// Kill the `child`. The processes it started might keep running as only Unix has process groups.
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    kill_process(child);
+    let _res = child.wait();
+}
+
+fn kill_process(child: &mut Child) {
+    if let Err(err) = child.kill() {
+        log::warn!("Could not kill the process {}: {err}", child.id());
+    }
+}
+
+fn read_in_thread(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut output = String::new();
+        let _res = pipe.read_to_string(&mut output);
+        output
+    })
+}
+
+/// Ignore the differences that are not a move, e.g. http vs. https (we have a separate report for those).
+pub fn normalize_repository_url(url: &str) -> String {
+    let url = url.to_lowercase();
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(&url);
+    let url = url.strip_prefix("www.").unwrap_or(url);
+    let url = url.trim_end_matches('/');
+    url.strip_suffix(".git").unwrap_or(url).to_owned()
+}
+
+/// The entries `depth` levels deep in `root` (e.g. `host/owner/repo`) that are not in `expected`.
+///
+/// Files we find above that level are ignored, e.g. the details of the packages in `repo-details/crates-io/`.
+pub fn find_orphans<S: BuildHasher>(
+    root: &Path,
+    depth: usize,
+    expected: &HashSet<PathBuf, S>,
+) -> Vec<PathBuf> {
+    let mut orphans = vec![];
+    let Ok(entries) = fs::read_dir(root) else {
+        return orphans;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if depth <= 1 {
+            if !expected.contains(&path) {
+                orphans.push(path);
+            }
+            continue;
+        }
+        if path.is_dir() {
+            orphans.extend(find_orphans(&path, depth - 1, expected));
+        }
+    }
+    orphans.sort();
+    orphans
+}
+
+/// Remove the file or folder at `path` and the folders above it (up to `root`) that became empty.
+///
+/// # Errors
+///
+/// Will return `Err` if we could not remove `path`.
+pub fn remove_orphan(root: &Path, path: &Path) -> Result<(), String> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.map_err(|err| format!("Could not remove {}: {err}", path.display()))?;
+
+    for parent in path.ancestors().skip(1) {
+        if parent == root || !parent.starts_with(root) {
+            break;
+        }
+        // fails if the folder is not empty
+        if fs::remove_dir(parent).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The total size of the files in a folder in bytes.
+pub fn dir_size(path: &Path) -> u64 {
+    let entries = match path.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// The latest, not yanked version of each crate by `crate_id`.
+///
+/// # Errors
+///
+/// Will return `Err` if can't open `versions.csv` or if it is not a
+/// proper CSV file.
+pub fn read_latest_versions() -> Result<HashMap<String, Version>, String> {
+    let filepath = "data/data/versions.csv";
+    log::info!("Start reading {}", filepath);
+    let mut versions: HashMap<String, Version> = HashMap::new();
+    match File::open(filepath) {
+        Ok(file) => {
+            let mut rdr = csv::Reader::from_reader(file);
+            for result in rdr.deserialize() {
+                let record: Version = match result {
+                    Ok(value) => value,
+                    Err(error) => return Err(format!("error: {error}")),
+                };
+                if record.yanked == "t" {
+                    continue;
+                }
+                let is_newer = versions
+                    .get(&record.crate_id)
+                    .is_none_or(|latest| latest.created_at < record.created_at);
+                if is_newer {
+                    versions.insert(record.crate_id.clone(), record);
+                }
+            }
+        }
+        Err(error) => return Err(format!("Error opening file {filepath}: {error}")),
+    }
+
+    log::info!("Finished reading {filepath}");
+    Ok(versions)
+}
+
 pub fn build_path(mut path: PathBuf, parts: &[&str], extension: Option<&str>) -> PathBuf {
     for part in parts {
         path = path.join(part);
@@ -359,6 +1344,34 @@ pub fn build_path(mut path: PathBuf, parts: &[&str], extension: Option<&str>) ->
     path
 }
 
+/// The `path` given relative to the folder `dir` (e.g. in a `Cargo.toml`) as a path relative to the root of the repository.
+///
+/// The `.` and `..` parts are resolved, the ones that would leave the repository are dropped.
+pub fn path_in_repository(dir: &str, path: &str) -> String {
+    let mut normalized = PathBuf::new();
+    for component in Path::new(dir).join(path).components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::Normal(name) => normalized.push(name),
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    normalized.to_string_lossy().to_string()
+}
+
+/// An empty folder for the files created by a test.
+#[cfg(test)]
+fn temp_folder(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("rust-digger-{}-{name}", std::process::id()));
+    if path.exists() {
+        fs::remove_dir_all(&path).unwrap();
+    }
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -408,6 +1421,357 @@ mod tests {
                 "rust-digger".to_string()
             )
         );
+        assert_eq!(
+            get_owner_and_repo("https://chiselapp.com/user/Foo/repository/bar/home"),
+            ("chiselapp".to_owned(), "foo".to_owned(), "bar".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_git_error_from_stderr() {
+        assert_eq!(
+            GitError::from_stderr("remote: Repository not found.\nfatal: repository 'https://github.com/foo/bar/' not found"),
+            GitError::NotFound
+        );
+        assert_eq!(
+            GitError::from_stderr("fatal: repository 'https://git.example.org/foo/bar/' not found"),
+            GitError::NotFound
+        );
+        assert_eq!(
+            GitError::from_stderr("abort: HTTP Error 404: Not Found"),
+            GitError::NotFound
+        );
+        // the tools are missing, not the repository
+        assert_eq!(
+            GitError::from_stderr("git: 'remote-hg' is not a git command. See 'git --help'.\nfatal: remote helper 'hg' not found"),
+            GitError::Other
+        );
+        assert_eq!(
+            GitError::from_stderr("sh: 1: fossil: command not found"),
+            GitError::Other
+        );
+        assert_eq!(
+            GitError::from_stderr("fatal: could not read Username for 'https://github.com': terminal prompts disabled"),
+            GitError::AuthRequired
+        );
+        assert_eq!(
+            GitError::from_stderr("fatal: unable to access 'https://gitlab.com/foo/bar/': Failed to connect to gitlab.com port 443: Connection timed out"),
+            GitError::Timeout
+        );
+        assert_eq!(
+            GitError::from_stderr("fatal: unable to access 'https://git.example.org/foo/': Could not resolve host: git.example.org"),
+            GitError::Network
+        );
+        assert_eq!(
+            GitError::from_stderr("fatal: write error: No space left on device"),
+            GitError::TooLarge
+        );
+        assert_eq!(
+            GitError::from_stderr("fatal: something else"),
+            GitError::Other
+        );
+    }
+
+    #[test]
+    fn test_repo_state_from_files() {
+        let files = |files: &[&str]| {
+            files
+                .iter()
+                .map(|&file| file.to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(RepoState::from_files(&[]), RepoState::NoRustFiles);
+        assert_eq!(
+            RepoState::from_files(&files(&["README.md", "Cargo.toml"])),
+            RepoState::NoRustFiles
+        );
+        assert_eq!(
+            RepoState::from_files(&files(&["README.md", "src/main.rs", "MyCargo.toml"])),
+            RepoState::NoCargoToml
+        );
+        assert_eq!(
+            RepoState::from_files(&files(&["crates/foo/Cargo.toml", "crates/foo/src/lib.rs"])),
+            RepoState::Ok
+        );
+    }
+
+    #[test]
+    fn test_clone_retry_due() {
+        let now = Utc::now();
+        let mut details = Details::new();
+        assert!(details.clone_retry_due(now));
+
+        details.record_clone_error(GitError::NotFound, now);
+        assert_eq!(details.git_clone_error, "not-found");
+        assert_eq!(details.git_clone_attempts, 1);
+        assert!(!details.clone_retry_due(now));
+        assert!(!details.clone_retry_due(now + Duration::try_hours(23).unwrap()));
+        assert!(details.clone_retry_due(now + Duration::try_hours(24).unwrap()));
+
+        details.record_clone_error(GitError::NotFound, now);
+        details.record_clone_error(GitError::NotFound, now);
+        assert_eq!(details.git_clone_attempts, 3);
+        assert!(!details.clone_retry_due(now + Duration::try_days(3).unwrap()));
+        assert!(details.clone_retry_due(now + Duration::try_days(4).unwrap()));
+
+        details.git_clone_attempts = 100;
+        assert!(details.clone_retry_due(now + Duration::try_days(64).unwrap()));
+
+        details.clear_clone_error();
+        assert!(details.git_clone_error.is_empty());
+        assert_eq!(details.git_clone_attempts, 0);
+        assert!(details.clone_retry_due(now));
+    }
+
+    #[test]
+    fn test_is_analysed() {
+        let mut details = Details::new();
+        assert!(!details.is_analysed(""));
+        details.analysed_head = "abc".to_owned();
+        assert!(!details.is_analysed("abc"));
+        details.analysis_version = ANALYSIS_VERSION;
+        assert!(details.is_analysed("abc"));
+        assert!(!details.is_analysed("def"));
+    }
+
+    #[test]
+    fn test_get_clone_url() {
+        assert_eq!(
+            get_clone_url("https://github.com/Szabgab/rust-digger/"),
+            Some("https://github.com/Szabgab/rust-digger".to_owned())
+        );
+        assert_eq!(
+            get_clone_url("https://gitlab.com/foo/bar/-/tree/master/crates/qux"),
+            Some("https://gitlab.com/foo/bar".to_owned())
+        );
+        assert_eq!(
+            get_clone_url("https://hg.mozilla.org/projects/Foo/file/tip"),
+            Some("https://hg.mozilla.org/projects/Foo".to_owned())
+        );
+        assert_eq!(
+            get_clone_url("https://chiselapp.com/user/foo/repository/bar/home"),
+            Some("https://chiselapp.com/user/foo/repository/bar".to_owned())
+        );
+        assert_eq!(get_clone_url("https://zorg.com/foo/bar"), None);
+    }
+
+    #[test]
+    fn test_get_clone_url_of_forges() {
+        let cases = [
+            // Forgejo
+            (
+                "https://codeberg.org/foo/bar/src/branch/main/crates/qux",
+                ("codeberg", "foo", "bar"),
+                "https://codeberg.org/foo/bar",
+            ),
+            // Gitea
+            (
+                "https://gitea.com/foo/bar",
+                ("gitea", "foo", "bar"),
+                "https://gitea.com/foo/bar",
+            ),
+            // Gitea in a subfolder
+            (
+                "https://apple-bear.com/gitea/foo/bar",
+                ("apple_bear", "foo", "bar"),
+                "https://apple-bear.com/gitea/foo/bar",
+            ),
+            // self-hosted GitLab
+            (
+                "https://salsa.debian.org/rust-team/debcargo/-/tree/master",
+                ("debian", "rust-team", "debcargo"),
+                "https://salsa.debian.org/rust-team/debcargo",
+            ),
+            // Gogs
+            (
+                "https://git.swzry.com/foo/bar",
+                ("swzry.com", "foo", "bar"),
+                "https://git.swzry.com/foo/bar",
+            ),
+            // no platform in repo_types.yaml
+            (
+                "https://bitbucket.org/foo/bar/src/master/",
+                ("bitbucket", "foo", "bar"),
+                "https://bitbucket.org/foo/bar",
+            ),
+            (
+                "https://gitee.com/foo/bar",
+                ("gitee", "foo", "bar"),
+                "https://gitee.com/foo/bar",
+            ),
+            (
+                "https://git.sr.ht/~foo/bar/tree",
+                ("srht", "~foo", "bar"),
+                "https://git.sr.ht/~foo/bar",
+            ),
+            (
+                "https://sr.ht/~foo/bar/",
+                ("sourcehut", "~foo", "bar"),
+                "https://git.sr.ht/~foo/bar",
+            ),
+            (
+                "https://sourceforge.net/projects/foo/",
+                ("sourceforge", "foo", "foo"),
+                "https://git.code.sf.net/p/foo/code",
+            ),
+            // Cgit
+            (
+                "https://git.kernel.org/pub/scm/linux/kernel/git/foo/bar.git/tree/",
+                ("kernel", "foo", "bar.git"),
+                "https://git.kernel.org/pub/scm/linux/kernel/git/foo/bar.git",
+            ),
+            (
+                "https://git.tozt.net/rbw/about/",
+                ("tozt", "tozt", "rbw"),
+                "https://git.tozt.net/rbw",
+            ),
+            // Mercurial
+            (
+                "https://hg.mozilla.org/projects/foo",
+                ("mozilla", "projects", "foo"),
+                "https://hg.mozilla.org/projects/foo",
+            ),
+            // Fossil
+            (
+                "https://chiselapp.com/user/foo/repository/bar",
+                ("chiselapp", "foo", "bar"),
+                "https://chiselapp.com/user/foo/repository/bar",
+            ),
+        ];
+        for (repository, (host, owner, repo), clone_url) in cases {
+            assert_eq!(
+                get_owner_and_repo(repository),
+                (host.to_owned(), owner.to_owned(), repo.to_owned()),
+                "{repository}"
+            );
+            assert_eq!(
+                get_clone_url(repository),
+                Some(clone_url.to_owned()),
+                "{repository}"
+            );
+        }
+
+        assert_eq!(get_clone_url("https://codeberg.org/foo"), None);
+        assert_eq!(get_clone_url("http://github.com/foo/bar"), None);
+        assert_eq!(get_clone_url("https://www.github.com/foo/bar"), None);
+    }
+
+    #[test]
+    fn test_get_crate_subdir() {
+        assert_eq!(
+            get_crate_subdir("https://github.com/szabgab/rust-digger"),
+            None
+        );
+        assert_eq!(
+            get_crate_subdir("https://github.com/szabgab/rust-digger/tree/main"),
+            None
+        );
+        assert_eq!(
+            get_crate_subdir(
+                "https://github.com/crypto-crawler/crypto-crawler-rs/tree/main/crypto-market-type"
+            ),
+            Some("crypto-market-type".to_owned())
+        );
+        assert_eq!(
+            get_crate_subdir("https://gitlab.com/foo/bar/-/tree/master/crates/qux/"),
+            Some("crates/qux".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_find_and_remove_orphans() {
+        let root = temp_folder("orphans");
+        for path in ["github/foo/bar", "github/foo/old", "gitlab/qux/old"] {
+            fs::create_dir_all(root.join(path)).unwrap();
+        }
+        fs::write(root.join("github/README.md"), "").unwrap();
+
+        let expected = HashSet::from([root.join("github/foo/bar"), root.join("github/foo/qux")]);
+        let orphans = find_orphans(&root, 3, &expected);
+        assert_eq!(
+            orphans,
+            vec![root.join("github/foo/old"), root.join("gitlab/qux/old")]
+        );
+
+        for orphan in &orphans {
+            remove_orphan(&root, orphan).unwrap();
+        }
+        assert!(root.join("github/foo/bar").exists());
+        assert!(root.join("github/README.md").exists());
+        assert!(!root.join("github/foo/old").exists());
+        assert!(!root.join("gitlab").exists());
+        assert!(root.exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_normalize_repository_url() {
+        assert_eq!(
+            normalize_repository_url("http://www.github.com/Foo/Bar.git/"),
+            "github.com/foo/bar"
+        );
+        assert_eq!(
+            normalize_repository_url("https://github.com/foo/bar"),
+            "github.com/foo/bar"
+        );
+    }
+
+    #[test]
+    fn test_run_command() {
+        let mut command = git_command();
+        command.arg("--version");
+        let stdout = run_command(command, Path::new("."), 10, 0).unwrap();
+        assert!(stdout.starts_with("git version"));
+
+        let mut command = git_command();
+        command.arg("no-such-command");
+        assert_eq!(
+            run_command(command, Path::new("."), 10, 0),
+            Err(GitError::Other)
+        );
+    }
+
+    #[test]
+    fn test_run_command_timeout() {
+        // a git alias running a shell command that takes longer than the timeout
+        let mut command = git_command();
+        command.arg("-c").arg("alias.wait=!sleep 5").arg("wait");
+        assert_eq!(
+            run_command(command, Path::new("."), 1, 0),
+            Err(GitError::Timeout)
+        );
+
+        // the processes started by git are killed as well
+        let root = temp_folder("run-command-timeout");
+        let pid_file = root.join("pid");
+        let mut command = git_command();
+        command
+            .arg("-c")
+            .arg(format!(
+                "alias.wait=!sleep 30 & echo $! > {}; wait",
+                pid_file.display()
+            ))
+            .arg("wait");
+        assert_eq!(
+            run_command(command, Path::new("."), 1, 0),
+            Err(GitError::Timeout)
+        );
+        let stat = format!(
+            "/proc/{}/stat",
+            fs::read_to_string(&pid_file).unwrap().trim()
+        );
+        // a killed process might stay a zombie until init reaps it
+        let is_gone = || {
+            let state = fs::read_to_string(&stat).unwrap_or_default();
+            state.is_empty() || state.contains(") Z ")
+        };
+        for _ in 0..20 {
+            if is_gone() {
+                break;
+            }
+            std::thread::sleep(core::time::Duration::from_millis(100));
+        }
+        assert!(is_gone(), "sleep is still running");
     }
 
     #[test]
@@ -418,6 +1782,26 @@ mod tests {
         assert_eq!(percentage(1_234_567, 10_000_000), "12.34");
     }
 
+    #[test]
+    fn test_get_settings_path() {
+        assert_eq!(
+            get_settings_path("https://github.com/foo/bar"),
+            Some(
+                repo_details_root()
+                    .join("github")
+                    .join("foo")
+                    .join("bar.settings.jsonl")
+            )
+        );
+        assert_eq!(get_settings_path(""), None);
+        assert_eq!(
+            get_package_settings_path("bar"),
+            repo_details_root()
+                .join("crates-io")
+                .join("bar.settings.jsonl")
+        );
+    }
+
     #[test]
     fn test_get_details_path() {
         let expected = repo_details_root()
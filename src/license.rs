@@ -0,0 +1,367 @@
+use crate::path_in_repository;
+
+/// Licenses whose texts contain the phrases of another license, e.g. the LGPL texts also mention the GPL.
+/// The second one is not reported if the text matches the first one.
+const CONTAINED_LICENSES: &[(&str, &str)] = &[
+    ("BSD-3-Clause", "BSD-2-Clause"),
+    ("ISC", "0BSD"),
+    ("LGPL-2.1", "GPL-2.0"),
+    ("LGPL-3.0", "GPL-3.0"),
+];
+
+/// The names of the license files start with one of these. (Compared case-insensitively.)
+const LICENSE_FILE_PREFIXES: &[&str] = &["LICENSE", "LICENCE", "COPYING", "UNLICENSE"];
+
+/// Phrases from the texts of the common licenses. A license text matches if it contains all the phrases of an entry.
+const LICENSE_PHRASES: &[(&str, &[&str])] = &[
+    (
+        "Apache-2.0",
+        &["apache license", "version 2.0, january 2004"],
+    ),
+    ("Apache-2.0", &["licensed under the apache license, version 2.0"]),
+    (
+        "MIT",
+        &["permission is hereby granted, free of charge, to any person obtaining a copy"],
+    ),
+    (
+        "BSD-3-Clause",
+        &["redistribution and use in source and binary forms", "neither the name of"],
+    ),
+    ("BSD-2-Clause", &["redistribution and use in source and binary forms"]),
+    (
+        "ISC",
+        &[
+            "permission to use, copy, modify, and/or distribute this software for any purpose",
+            "provided that the above copyright notice and this permission notice appear in all copies",
+        ],
+    ),
+    (
+        "0BSD",
+        &["permission to use, copy, modify, and/or distribute this software for any purpose"],
+    ),
+    ("MPL-2.0", &["mozilla public license version 2.0"]),
+    ("MPL-2.0", &["mozilla public license, v. 2.0"]),
+    (
+        "AGPL-3.0",
+        &["gnu affero general public license", "version 3, 19 november 2007"],
+    ),
+    (
+        "LGPL-3.0",
+        &["gnu lesser general public license", "version 3, 29 june 2007"],
+    ),
+    (
+        "LGPL-2.1",
+        &["gnu lesser general public license", "version 2.1, february 1999"],
+    ),
+    (
+        "GPL-3.0",
+        &["gnu general public license", "version 3, 29 june 2007"],
+    ),
+    (
+        "GPL-2.0",
+        &["gnu general public license", "version 2, june 1991"],
+    ),
+    (
+        "Unlicense",
+        &["this is free and unencumbered software released into the public domain"],
+    ),
+    ("Zlib", &["altered source versions must be plainly marked as such"]),
+    ("BSL-1.0", &["boost software license - version 1.0"]),
+    ("CC0-1.0", &["cc0 1.0 universal"]),
+];
+
+/// The folder of the license files in the REUSE specification (<https://reuse.software/>), e.g. `LICENSES/MIT.txt`.
+const REUSE_FOLDER: &str = "LICENSES/";
+
+/// The problems with the license files of a crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LicenseProblem {
+    /// A license file contains a license that is not in the `license` field of the `Cargo.toml`.
+    Mismatch,
+    /// The `license` field lists several licenses, but there are fewer license files.
+    MissingDualFile,
+    /// There is no LICENSE or COPYING file.
+    MissingFile,
+    /// The `license` field of the `Cargo.toml` differs from the license of the latest release on Crates.io.
+    PublishedMismatch,
+}
+
+impl LicenseProblem {
+    pub const ALL: [Self; 4] = [
+        Self::MissingFile,
+        Self::Mismatch,
+        Self::MissingDualFile,
+        Self::PublishedMismatch,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::MissingFile => "missing-file",
+            Self::Mismatch => "mismatch",
+            Self::MissingDualFile => "missing-dual-file",
+            Self::PublishedMismatch => "published-mismatch",
+        }
+    }
+}
+
+/// The license files in the root of the repository and in the folder of the crate (`crate_dir`, relative to the root).
+///
+/// The files in the `LICENSES/` folder of the REUSE specification are included.
+/// The `license_file` of the `Cargo.toml` is included even if it has some other name.
+pub fn find_license_files(files: &[String], crate_dir: &str, license_file: &str) -> Vec<String> {
+    let prefix = if crate_dir.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", crate_dir.trim_end_matches('/'))
+    };
+    let declared = (!license_file.is_empty()).then(|| path_in_repository(crate_dir, license_file));
+
+    let is_license_file = |name: &str| {
+        name.strip_prefix(REUSE_FOLDER).map_or_else(
+            || {
+                !name.contains('/')
+                    && LICENSE_FILE_PREFIXES.iter().any(|license_prefix| {
+                        name.get(..license_prefix.len())
+                            .is_some_and(|start| start.eq_ignore_ascii_case(license_prefix))
+                    })
+            },
+            |reuse_file| !reuse_file.is_empty() && !reuse_file.contains('/'),
+        )
+    };
+    let mut license_files = files
+        .iter()
+        .filter(|file| {
+            is_license_file(file)
+                || file.strip_prefix(&prefix).is_some_and(is_license_file)
+                || declared.as_ref() == Some(*file)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    license_files.sort();
+    license_files.dedup();
+    license_files
+}
+
+/// Lowercase words separated by a single space so line breaks, indentation and Markdown markup don't matter.
+fn normalize_text(text: &str) -> String {
+    text.to_lowercase()
+        .split(|char: char| !char.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The SPDX identifiers of the common licenses in `text`, sorted. A file can contain several licenses.
+pub fn identify_license(text: &str) -> Vec<&'static str> {
+    let text = normalize_text(text);
+    let mut licenses = LICENSE_PHRASES
+        .iter()
+        .filter(|entry| {
+            entry
+                .1
+                .iter()
+                .all(|phrase| text.contains(&normalize_text(phrase)))
+        })
+        .map(|entry| entry.0)
+        .collect::<Vec<_>>();
+    licenses.sort_unstable();
+    licenses.dedup();
+    let contained = CONTAINED_LICENSES
+        .iter()
+        .filter(|pair| licenses.contains(&pair.0))
+        .map(|pair| pair.1)
+        .collect::<Vec<_>>();
+    licenses.retain(|license| !contained.contains(license));
+    licenses
+}
+
+/// The licenses in an SPDX expression, e.g. `MIT OR Apache-2.0`, sorted.
+///
+/// The `-only`, `-or-later` and `+` suffixes and the `WITH` exceptions are dropped.
+/// The `/` separator of the old Crates.io releases is treated like `OR`.
+pub fn spdx_ids(expression: &str) -> Vec<String> {
+    let mut ids = vec![];
+    let mut tokens = expression
+        .split(|char: char| char.is_whitespace() || matches!(char, '(' | ')' | '/'))
+        .filter(|token| !token.is_empty());
+    while let Some(token) = tokens.next() {
+        if token.eq_ignore_ascii_case("WITH") {
+            tokens.next();
+            continue;
+        }
+        if token.eq_ignore_ascii_case("OR") || token.eq_ignore_ascii_case("AND") {
+            continue;
+        }
+        let id = token
+            .trim_end_matches('+')
+            .trim_end_matches("-or-later")
+            .trim_end_matches("-only");
+        ids.push(id.to_owned());
+    }
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// Compare the `license` field of the `Cargo.toml` with the license files and with the latest release.
+///
+/// `found` has the licenses found in each of the license files (empty if we could not identify the text of a file) and
+/// `published` is the license of the latest release on Crates.io.
+pub fn find_license_problems(
+    license: &str,
+    found: &[Vec<&str>],
+    published: &str,
+) -> Vec<LicenseProblem> {
+    let mut problems = vec![];
+    if found.is_empty() {
+        problems.push(LicenseProblem::MissingFile);
+    }
+
+    let ids = spdx_ids(license);
+    if !ids.is_empty() {
+        let identified = found.iter().flatten().collect::<Vec<_>>();
+        if identified
+            .iter()
+            .any(|&&id| !ids.iter().any(|name| name == id))
+        {
+            problems.push(LicenseProblem::Mismatch);
+        }
+        // each file we could not identify might have one of the missing licenses
+        let covered = ids
+            .iter()
+            .filter(|name| identified.contains(&&name.as_str()))
+            .count();
+        let unidentified = found.iter().filter(|licenses| licenses.is_empty()).count();
+        if ids.len() > 1 && !identified.is_empty() && covered + unidentified < ids.len() {
+            problems.push(LicenseProblem::MissingDualFile);
+        }
+        if !published.is_empty() && spdx_ids(published) != ids {
+            problems.push(LicenseProblem::PublishedMismatch);
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_license_files() {
+        let files = [
+            "LICENSE-MIT",
+            "license-apache.md",
+            "COPYING",
+            "src/license.rs",
+            "crates/foo/LICENSE",
+            "crates/bar/LICENSE",
+            "crates/foo/legal/terms.txt",
+            "crates/qux/LICENSES/MIT.txt",
+            "LICENSES/Apache-2.0.txt",
+            "LICENSES/old/GPL-2.0.txt",
+            "legal/LICENSE.txt",
+            "README.md",
+        ]
+        .iter()
+        .map(|&file| file.to_owned())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            find_license_files(&files, "", ""),
+            vec![
+                "COPYING",
+                "LICENSE-MIT",
+                "LICENSES/Apache-2.0.txt",
+                "license-apache.md",
+            ]
+        );
+        assert_eq!(
+            find_license_files(&files, "crates/foo", "legal/terms.txt"),
+            vec![
+                "COPYING",
+                "LICENSE-MIT",
+                "LICENSES/Apache-2.0.txt",
+                "crates/foo/LICENSE",
+                "crates/foo/legal/terms.txt",
+                "license-apache.md",
+            ]
+        );
+        assert_eq!(
+            find_license_files(&files, "crates/qux", "../../legal/LICENSE.txt"),
+            vec![
+                "COPYING",
+                "LICENSE-MIT",
+                "LICENSES/Apache-2.0.txt",
+                "crates/qux/LICENSES/MIT.txt",
+                "legal/LICENSE.txt",
+                "license-apache.md",
+            ]
+        );
+        assert_eq!(find_license_files(&[], "", ""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_identify_license() {
+        let mit = "MIT License\n\nCopyright (c) 2024 Foo\n\nPermission is hereby granted, free of charge, to any\nperson obtaining a copy of this software";
+        assert_eq!(identify_license(mit), vec!["MIT"]);
+        let apache = "                                 Apache License\n                           Version 2.0, January 2004\n                        http://www.apache.org/licenses/";
+        assert_eq!(identify_license(apache), vec!["Apache-2.0"]);
+        let bsd = "Redistribution and use in source and binary forms, with or without modification, are permitted\n* Neither the name of the copyright holder";
+        assert_eq!(identify_license(bsd), vec!["BSD-3-Clause"]);
+        let lgpl = "GNU LESSER GENERAL PUBLIC LICENSE\nVersion 3, 29 June 2007\n... the GNU General Public License ...";
+        assert_eq!(identify_license(lgpl), vec!["LGPL-3.0"]);
+        let both = format!("{mit}\n\n{apache}");
+        assert_eq!(identify_license(&both), vec!["Apache-2.0", "MIT"]);
+        assert_eq!(identify_license("All rights reserved."), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_spdx_ids() {
+        assert_eq!(spdx_ids("MIT OR Apache-2.0"), vec!["Apache-2.0", "MIT"]);
+        assert_eq!(spdx_ids("MIT/Apache-2.0"), vec!["Apache-2.0", "MIT"]);
+        assert_eq!(
+            spdx_ids("(GPL-2.0-or-later WITH Classpath-exception-2.0) AND GPL-3.0+"),
+            vec!["GPL-2.0", "GPL-3.0"]
+        );
+        assert_eq!(spdx_ids(""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_find_license_problems() {
+        let dual = "MIT OR Apache-2.0";
+        assert_eq!(
+            find_license_problems(dual, &[vec!["Apache-2.0"], vec!["MIT"]], "MIT/Apache-2.0"),
+            vec![]
+        );
+        assert_eq!(
+            find_license_problems(dual, &[], ""),
+            vec![LicenseProblem::MissingFile]
+        );
+        assert_eq!(
+            find_license_problems(dual, &[vec!["MIT"]], ""),
+            vec![LicenseProblem::MissingDualFile]
+        );
+        // the MIT license in a file we could not identify
+        assert_eq!(
+            find_license_problems(dual, &[vec!["Apache-2.0"], vec![]], ""),
+            vec![]
+        );
+        assert_eq!(
+            find_license_problems(dual, &[vec!["MIT"], vec!["MIT"]], ""),
+            vec![LicenseProblem::MissingDualFile]
+        );
+        // a single file with both licenses
+        assert_eq!(
+            find_license_problems(dual, &[vec!["Apache-2.0", "MIT"]], ""),
+            vec![]
+        );
+        assert_eq!(
+            find_license_problems("MIT", &[vec!["GPL-3.0"]], "Apache-2.0"),
+            vec![LicenseProblem::Mismatch, LicenseProblem::PublishedMismatch]
+        );
+        assert_eq!(
+            find_license_problems("", &[vec![]], ""),
+            Vec::<LicenseProblem>::new()
+        );
+    }
+}
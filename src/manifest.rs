@@ -0,0 +1,839 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use toml::{Table, Value};
+
+use crate::{normalize_repository_url, Crate};
+
+/// The names cargo looks for if the `readme` field is missing.
+const README_FILES: &[&str] = &["README.md", "README.txt", "README"];
+
+/// Reads a file, from the disk or from the commit, as a sparse checkout has only some of the files on the disk.
+pub type ReadFile<'read> = &'read dyn Fn(&Path) -> Result<String, String>;
+
+/// The fields of a `Cargo.toml` file we collect, with the values inherited from the workspace already resolved.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub badges: Vec<String>,
+    pub build_script: bool,
+    pub categories: Vec<String>,
+    pub description: String,
+    pub documentation: String,
+    pub edition: String,
+    pub features: Vec<String>,
+    pub homepage: String,
+    pub keywords: Vec<String>,
+    pub license: String,
+    pub license_file: String,
+    pub links: String,
+    /// The tools in the `[lints]` table, e.g. `clippy`, or `workspace` if the lints are inherited from the workspace.
+    pub lints: Vec<String>,
+    /// Empty if the `Cargo.toml` has no `[package]`, e.g. in a virtual workspace.
+    pub name: String,
+    /// `false` if the crate must not be published to any registry.
+    pub publish: bool,
+    pub readme: ManifestReadme,
+    pub repository: String,
+    pub rust_version: String,
+    /// `lib`, `proc-macro` and `bin`, including the ones cargo discovers without being listed.
+    pub targets: Vec<String>,
+    pub version: String,
+}
+
+/// The `readme` field of a `Cargo.toml`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum ManifestReadme {
+    /// `readme = false`, the package has no README.
+    Disabled,
+    /// The path in the `readme` field or the README file cargo would find, relative to the folder of the `Cargo.toml`.
+    Path(String),
+    /// There is no `readme` field and no README file cargo would find.
+    #[default]
+    Unset,
+}
+
+/// The `[workspace]` table of the `Cargo.toml` in the root of a repository.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Workspace {
+    /// The number of entries in `[workspace.dependencies]`.
+    pub dependencies: u32,
+    /// There is a `[workspace.lints]` table.
+    pub lints: bool,
+    /// The names of the packages in the workspace, including the root package.
+    pub members: Vec<String>,
+    /// The root `Cargo.toml` has no `[package]`.
+    pub virtual_manifest: bool,
+}
+
+/// The ways the `Cargo.toml` in the repository can differ from what was published on Crates.io.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Mismatch {
+    Description,
+    Documentation,
+    Homepage,
+    /// There is no package with the name of the crate in the repository.
+    NameNotFound,
+    Repository,
+    /// The version in the repository is lower than the latest published version.
+    VersionLower,
+}
+
+impl Mismatch {
+    pub const ALL: [Self; 6] = [
+        Self::NameNotFound,
+        Self::Repository,
+        Self::Homepage,
+        Self::Documentation,
+        Self::Description,
+        Self::VersionLower,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::NameNotFound => "name-not-found",
+            Self::Repository => "repository",
+            Self::Homepage => "homepage",
+            Self::Documentation => "documentation",
+            Self::Description => "description",
+            Self::VersionLower => "version-lower",
+        }
+    }
+}
+
+/// Load the `Cargo.toml` file in `dir`.
+///
+/// Fields such as `edition.workspace = true` are taken from the `[workspace.package]` table of the
+/// workspace root. That is either the folder in the `package.workspace` field or the closest folder
+/// above `dir` (but not above `root`) that has a `Cargo.toml` with a `[workspace]` table.
+///
+/// # Errors
+///
+/// Will return `Err` if there is no `Cargo.toml` in `dir` or it is not a proper TOML file.
+pub fn load_manifest(dir: &Path, root: &Path) -> Result<Manifest, String> {
+    load_manifest_with(dir, root, &read_from_disk)
+}
+
+/// Same as `load_manifest`, but the `Cargo.toml` files are read by `read`.
+///
+/// The README, the `build.rs` and the targets next to the `Cargo.toml` are still looked for on the disk.
+fn load_manifest_with(dir: &Path, root: &Path, read: ReadFile) -> Result<Manifest, String> {
+    let table = load_toml(&dir.join("Cargo.toml"), read)?;
+    let mut manifest = Manifest::default();
+    let Some(package) = table.get("package").and_then(Value::as_table) else {
+        return Ok(manifest);
+    };
+
+    let workspace_package = find_workspace_package(&table, package, dir, root, read);
+    let field = |name: &str| -> Option<&Value> {
+        let value = package.get(name)?;
+        if value.as_table().is_some_and(is_inherited) {
+            return workspace_package.as_ref()?.get(name);
+        }
+        Some(value)
+    };
+    let string = |name: &str| -> String {
+        field(name)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_owned()
+    };
+    let strings = |name: &str| -> Vec<String> { to_strings(field(name)) };
+    // the paths in `[workspace.package]` are relative to the root of the workspace
+    let path = |name: &str| -> Option<String> {
+        let path = PathBuf::from(field(name)?.as_str()?);
+        let inherited = package
+            .get(name)
+            .and_then(Value::as_table)
+            .is_some_and(is_inherited);
+        let path = if inherited {
+            find_workspace_dir(&table, package, dir, root, read)?.join(path)
+        } else {
+            path
+        };
+        Some(path.to_string_lossy().to_string())
+    };
+
+    manifest.name = string("name");
+    manifest.version = string("version");
+    manifest.repository = string("repository");
+    manifest.homepage = string("homepage");
+    manifest.documentation = string("documentation");
+    manifest.edition = string("edition");
+    manifest.rust_version = string("rust-version");
+    manifest.license = string("license");
+    manifest.license_file = path("license-file").unwrap_or_default();
+    manifest.description = string("description");
+    manifest.categories = strings("categories");
+    manifest.keywords = strings("keywords");
+    manifest.readme =
+        path("readme").map_or_else(|| get_readme(field("readme"), dir), ManifestReadme::Path);
+    manifest.publish = match field("publish") {
+        Some(&Value::Boolean(publish)) => publish,
+        Some(registries) => !to_strings(Some(registries)).is_empty(),
+        None => true,
+    };
+    manifest.links = string("links");
+    manifest.build_script = match package.get("build") {
+        Some(&Value::Boolean(build)) => build,
+        Some(_) => true,
+        None => dir.join("build.rs").exists(),
+    };
+    manifest.features = table
+        .get("features")
+        .and_then(Value::as_table)
+        .map(|features| features.keys().cloned().collect())
+        .unwrap_or_default();
+    manifest.badges = table
+        .get("badges")
+        .and_then(Value::as_table)
+        .map(|badges| badges.keys().cloned().collect())
+        .unwrap_or_default();
+    manifest.lints = table
+        .get("lints")
+        .and_then(Value::as_table)
+        .map(|lints| {
+            if is_inherited(lints) {
+                return vec![String::from("workspace")];
+            }
+            lints
+                .iter()
+                .filter(|entry| entry.1.is_table())
+                .map(|entry| entry.0.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    manifest.targets = get_targets(&table, package, dir);
+    Ok(manifest)
+}
+
+/// The level of each lint in the `[lints]` table of the `Cargo.toml` in `dir`, e.g. `clippy::pedantic` and `warn`.
+///
+/// If the package inherits the lints, they are taken from the `[workspace.lints]` table of the workspace it belongs to.
+/// (See `load_manifest` for the way we find the workspace.) The lints of rustc have no `rust::` prefix.
+///
+/// # Errors
+///
+/// Will return `Err` if there is no `Cargo.toml` in `dir` or it is not a proper TOML file.
+pub fn load_lints(dir: &Path, root: &Path) -> Result<Vec<(String, String)>, String> {
+    let table = load_toml(&dir.join("Cargo.toml"), &read_from_disk)?;
+    let Some(lints) = table.get("lints").and_then(Value::as_table) else {
+        return Ok(vec![]);
+    };
+    let lints = if is_inherited(lints) {
+        let package = table
+            .get("package")
+            .and_then(Value::as_table)
+            .cloned()
+            .unwrap_or_default();
+        find_workspace_table(&table, &package, dir, root, "lints", &read_from_disk)
+            .unwrap_or_default()
+    } else {
+        lints.clone()
+    };
+
+    let mut levels = vec![];
+    for (tool, tool_lints) in &lints {
+        let Some(tool_lints) = tool_lints.as_table() else {
+            continue;
+        };
+        for (lint, value) in tool_lints {
+            // either `lint = "warn"` or `lint = { level = "warn", priority = -1 }`
+            let Some(level) = value
+                .as_str()
+                .or_else(|| value.get("level").and_then(Value::as_str))
+            else {
+                continue;
+            };
+            let name = if tool == "rust" {
+                lint.clone()
+            } else {
+                format!("{tool}::{lint}")
+            };
+            levels.push((name, level.to_owned()));
+        }
+    }
+    Ok(levels)
+}
+
+/// Load all the `Cargo.toml` files among the `files` of the repository in `root` by the name of the package.
+/// (Cargo.toml files without a `[package]` are skipped.)
+///
+/// The `Cargo.toml` files are read by `read`. (See `load_manifest_with`.)
+pub fn find_manifests(root: &Path, files: &[String], read: ReadFile) -> HashMap<String, Manifest> {
+    let mut manifests = HashMap::new();
+    for file in files {
+        let Some(dir) = file.strip_suffix("Cargo.toml") else {
+            continue;
+        };
+        if !dir.is_empty() && !dir.ends_with('/') {
+            continue;
+        }
+        match load_manifest_with(&root.join(dir), root, read) {
+            Ok(manifest) if !manifest.name.is_empty() => {
+                manifests.insert(manifest.name.clone(), manifest);
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("{err}"),
+        }
+    }
+    manifests
+}
+
+/// Load the `[workspace]` of the `Cargo.toml` in `root`.
+///
+/// The members are the packages among the `files` of the repository that match the `members` globs
+/// and do not match the `exclude` list. The `Cargo.toml` files are read by `read`.
+///
+/// # Errors
+///
+/// Will return `Err` if there is no `Cargo.toml` in `root` or it is not a proper TOML file.
+pub fn load_workspace(
+    root: &Path,
+    files: &[String],
+    read: ReadFile,
+) -> Result<Option<Workspace>, String> {
+    let table = load_toml(&root.join("Cargo.toml"), read)?;
+    let Some(workspace) = table.get("workspace").and_then(Value::as_table) else {
+        return Ok(None);
+    };
+
+    let package_name = |table: &Table| -> Option<String> {
+        table
+            .get("package")?
+            .get("name")?
+            .as_str()
+            .map(str::to_owned)
+    };
+
+    let patterns = |name: &str| -> Vec<String> {
+        to_strings(workspace.get(name))
+            .iter()
+            .map(|pattern| normalize_member_path(pattern))
+            .collect()
+    };
+    let members = patterns("members");
+    let exclude = patterns("exclude");
+
+    let mut names = vec![];
+    if let Some(name) = package_name(&table) {
+        names.push(name);
+    }
+    for file in files {
+        let Some(dir) = file.strip_suffix("/Cargo.toml") else {
+            continue;
+        };
+        if !members.iter().any(|pattern| matches_glob(pattern, dir))
+            || exclude.iter().any(|path| path == dir)
+        {
+            continue;
+        }
+        match load_toml(&root.join(file), read) {
+            Ok(member) => names.extend(package_name(&member)),
+            Err(err) => log::warn!("{err}"),
+        }
+    }
+    names.sort();
+    names.dedup();
+
+    Ok(Some(Workspace {
+        virtual_manifest: !table.contains_key("package"),
+        members: names,
+        lints: workspace.get("lints").is_some_and(Value::is_table),
+        dependencies: workspace
+            .get("dependencies")
+            .and_then(Value::as_table)
+            .map_or(0, |dependencies| {
+                u32::try_from(dependencies.len()).unwrap_or(u32::MAX)
+            }),
+    }))
+}
+
+fn normalize_member_path(path: &str) -> String {
+    path.trim_start_matches("./")
+        .trim_end_matches('/')
+        .to_owned()
+}
+
+/// Match the `path` of a folder to a `members` entry where `*` and `?` can match any part of a single folder name.
+fn matches_glob(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[u8], text: &[u8]) -> bool {
+        match (pattern.first(), text.first()) {
+            (None, None) => true,
+            (Some(&b'*'), _) => {
+                matches(&pattern[1..], text) || (!text.is_empty() && matches(pattern, &text[1..]))
+            }
+            (Some(&b'?'), Some(_)) => matches(&pattern[1..], &text[1..]),
+            (Some(char), Some(other)) if char == other => matches(&pattern[1..], &text[1..]),
+            _ => false,
+        }
+    }
+
+    let pattern_parts = pattern.split('/').collect::<Vec<_>>();
+    let path_parts = path.split('/').collect::<Vec<_>>();
+    pattern_parts.len() == path_parts.len()
+        && pattern_parts
+            .iter()
+            .zip(&path_parts)
+            .all(|(part, name)| matches(part.as_bytes(), name.as_bytes()))
+}
+
+/// Compare the data of the crate on Crates.io with the `manifest` of the package of the same name in the repository.
+pub fn find_mismatches(
+    krate: &Crate,
+    latest_version: &str,
+    manifest: Option<&Manifest>,
+) -> Vec<Mismatch> {
+    let Some(manifest) = manifest else {
+        return vec![Mismatch::NameNotFound];
+    };
+
+    let same_url = |published: &str, current: &str| {
+        published.trim().trim_end_matches('/') == current.trim().trim_end_matches('/')
+    };
+    let same_text = |published: &str, current: &str| {
+        published.split_whitespace().eq(current.split_whitespace())
+    };
+
+    let mut mismatches = vec![];
+    if normalize_repository_url(krate.repository.trim())
+        != normalize_repository_url(manifest.repository.trim())
+    {
+        mismatches.push(Mismatch::Repository);
+    }
+    if !same_url(&krate.homepage, &manifest.homepage) {
+        mismatches.push(Mismatch::Homepage);
+    }
+    if !same_url(&krate.documentation, &manifest.documentation) {
+        mismatches.push(Mismatch::Documentation);
+    }
+    if !same_text(&krate.description, &manifest.description) {
+        mismatches.push(Mismatch::Description);
+    }
+    if let (Some(published), Some(current)) = (
+        parse_version(latest_version),
+        parse_version(&manifest.version),
+    ) {
+        if current < published {
+            mismatches.push(Mismatch::VersionLower);
+        }
+    }
+    mismatches
+}
+
+/// The numeric parts of a version number without the pre-release and build metadata.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.split(['-', '+']).next()?;
+    version
+        .split('.')
+        .map(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+fn to_strings(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The `readme` field if it is not a path.
+fn get_readme(value: Option<&Value>, dir: &Path) -> ManifestReadme {
+    match value {
+        Some(&Value::Boolean(false)) => ManifestReadme::Disabled,
+        Some(_) | None => README_FILES
+            .iter()
+            .find(|file| dir.join(file).exists())
+            .map_or(ManifestReadme::Unset, |&file| {
+                ManifestReadme::Path(file.to_owned())
+            }),
+    }
+}
+
+/// The kinds of the targets based on the `[lib]` and `[[bin]]` sections and the files cargo discovers.
+fn get_targets(table: &Table, package: &Table, dir: &Path) -> Vec<String> {
+    let mut targets = vec![];
+
+    let lib = table.get("lib");
+    if lib.is_some() || dir.join("src/lib.rs").exists() {
+        let proc_macro = lib
+            .and_then(|lib| lib.get("proc-macro").or_else(|| lib.get("proc_macro")))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        targets.push(String::from(if proc_macro { "proc-macro" } else { "lib" }));
+    }
+
+    let has_bins = table
+        .get("bin")
+        .and_then(Value::as_array)
+        .is_some_and(|bins| !bins.is_empty());
+    let autobins = package
+        .get("autobins")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    if has_bins || (autobins && (dir.join("src/main.rs").exists() || dir.join("src/bin").is_dir()))
+    {
+        targets.push(String::from("bin"));
+    }
+
+    targets
+}
+
+fn is_inherited(table: &Table) -> bool {
+    table.get("workspace").and_then(Value::as_bool) == Some(true)
+}
+
+/// The `[workspace.package]` table of the workspace the package belongs to.
+fn find_workspace_package(
+    table: &Table,
+    package: &Table,
+    dir: &Path,
+    root: &Path,
+    read: ReadFile,
+) -> Option<Table> {
+    find_workspace_table(table, package, dir, root, "package", read)
+}
+
+/// The `[workspace.{name}]` table of the workspace the package belongs to.
+fn find_workspace_table(
+    table: &Table,
+    package: &Table,
+    dir: &Path,
+    root: &Path,
+    name: &str,
+    read: ReadFile,
+) -> Option<Table> {
+    let workspace_dir = find_workspace_dir(table, package, dir, root, read)?;
+    let workspace = if workspace_dir.as_os_str().is_empty() {
+        table.clone()
+    } else {
+        load_toml(&dir.join(workspace_dir).join("Cargo.toml"), read).ok()?
+    };
+
+    workspace
+        .get("workspace")
+        .and_then(|workspace| workspace.get(name))
+        .and_then(Value::as_table)
+        .cloned()
+}
+
+/// The root of the workspace the package belongs to, relative to `dir`. Empty if it is `dir` itself.
+fn find_workspace_dir(
+    table: &Table,
+    package: &Table,
+    dir: &Path,
+    root: &Path,
+    read: ReadFile,
+) -> Option<PathBuf> {
+    if table.contains_key("workspace") {
+        return Some(PathBuf::new());
+    }
+    if let Some(path) = package.get("workspace").and_then(Value::as_str) {
+        return Some(PathBuf::from(path));
+    }
+    let workspace_root = find_workspace_root(dir, root, read)?;
+    let depth = dir.strip_prefix(workspace_root).ok()?.components().count();
+    Some(vec![Component::ParentDir; depth].iter().collect())
+}
+
+fn find_workspace_root(dir: &Path, root: &Path, read: ReadFile) -> Option<PathBuf> {
+    dir.ancestors()
+        .skip(1)
+        .take_while(|path| path.starts_with(root))
+        .find(|path| {
+            load_toml(&path.join("Cargo.toml"), read)
+                .is_ok_and(|table| table.contains_key("workspace"))
+        })
+        .map(Path::to_path_buf)
+}
+
+/// Read the file at `path` from the disk.
+///
+/// # Errors
+///
+/// Will return `Err` if the file could not be read.
+pub fn read_from_disk(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("Could not read {}: {err}", path.display()))
+}
+
+fn load_toml(path: &Path, read: ReadFile) -> Result<Table, String> {
+    let content = read(path)?;
+    content
+        .parse::<Table>()
+        .map_err(|err| format!("Error: {err} when parsing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from("tests/fixtures/manifests")
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|&value| value.to_owned()).collect()
+    }
+
+    #[test]
+    fn test_load_manifest() {
+        let root = fixtures().join("simple");
+        let manifest = load_manifest(&root, &root).unwrap();
+        assert_eq!(manifest.name, "simple");
+        assert_eq!(manifest.edition, "2021");
+        assert_eq!(manifest.rust_version, "1.70");
+        assert!(manifest.publish);
+        assert!(!manifest.build_script);
+        assert_eq!(manifest.readme, ManifestReadme::Unset);
+        assert_eq!(manifest.targets, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_load_manifest_all_fields() {
+        let root = fixtures().join("full");
+        assert_eq!(
+            load_manifest(&root, &root),
+            Ok(Manifest {
+                name: "full".to_owned(),
+                version: "1.0.0".to_owned(),
+                edition: "2021".to_owned(),
+                rust_version: String::new(),
+                license: "MIT OR Apache-2.0".to_owned(),
+                license_file: String::new(),
+                description: "A crate with all the fields we collect".to_owned(),
+                repository: "https://github.com/foo/full".to_owned(),
+                homepage: String::new(),
+                documentation: "https://docs.rs/full".to_owned(),
+                categories: strings(&["development-tools", "parsing"]),
+                keywords: strings(&["demo"]),
+                readme: ManifestReadme::Path("README.md".to_owned()),
+                publish: true,
+                features: strings(&["default", "serde", "std"]),
+                targets: strings(&["lib", "bin"]),
+                build_script: true,
+                links: "z".to_owned(),
+                badges: strings(&["maintenance"]),
+                lints: strings(&["clippy", "rust"]),
+            })
+        );
+        assert_eq!(
+            load_lints(&root, &root),
+            Ok(vec![
+                ("clippy::pedantic".to_owned(), "warn".to_owned()),
+                ("unsafe_code".to_owned(), "forbid".to_owned()),
+            ])
+        );
+        assert_eq!(load_lints(&fixtures().join("simple"), &root), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_load_manifest_proc_macro() {
+        let root = fixtures().join("proc-macro");
+        let manifest = load_manifest(&root, &root).unwrap();
+        assert_eq!(manifest.license_file, "LICENSE.txt");
+        assert_eq!(manifest.readme, ManifestReadme::Disabled);
+        assert!(!manifest.publish);
+        assert!(!manifest.build_script);
+        assert_eq!(manifest.targets, strings(&["proc-macro"]));
+    }
+
+    #[test]
+    fn test_load_manifest_without_package() {
+        let root = fixtures().join("workspace");
+        assert_eq!(load_manifest(&root, &root), Ok(Manifest::default()));
+        load_manifest(&fixtures().join("missing"), &root).unwrap_err();
+    }
+
+    #[test]
+    fn test_load_manifest_inherited_from_workspace() {
+        let root = fixtures().join("workspace");
+        let manifest = load_manifest(&root.join("crates").join("foo"), &root).unwrap();
+        assert_eq!(manifest.edition, "2021");
+        assert_eq!(manifest.rust_version, "1.74");
+        assert_eq!(manifest.license, "MIT");
+        // the paths are relative to the workspace root
+        assert_eq!(manifest.license_file, "../../LICENSE");
+        assert_eq!(
+            manifest.readme,
+            ManifestReadme::Path("../../docs/README.md".to_owned())
+        );
+        assert_eq!(manifest.lints, vec!["workspace"]);
+        assert_eq!(
+            load_lints(&root.join("crates").join("foo"), &root),
+            Ok(vec![("unsafe_code".to_owned(), "forbid".to_owned())])
+        );
+
+        // we don't look for the workspace above the root of the repository
+        let root = root.join("crates");
+        let manifest = load_manifest(&root.join("foo"), &root).unwrap();
+        assert_eq!(manifest.name, "foo");
+        assert_eq!(manifest.edition, "");
+        assert_eq!(manifest.rust_version, "");
+        assert_eq!(manifest.license, "");
+    }
+
+    #[test]
+    fn test_load_manifest_with_workspace_path() {
+        let root = fixtures().join("workspace-path");
+        let manifest = load_manifest(&root.join("bar"), &root).unwrap();
+        assert_eq!(manifest.edition, "2018");
+        assert_eq!(manifest.rust_version, "1.60");
+        assert_eq!(
+            manifest.readme,
+            ManifestReadme::Path("../ws/README.md".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_find_manifests() {
+        let root = fixtures().join("workspace");
+        let manifests = find_manifests(
+            &root,
+            &strings(&[
+                "Cargo.toml",
+                "crates/foo/Cargo.toml",
+                "crates/foo/MyCargo.toml",
+            ]),
+            &read_from_disk,
+        );
+        assert_eq!(manifests.keys().collect::<Vec<_>>(), vec!["foo"]);
+        assert_eq!(manifests["foo"].rust_version, "1.74");
+    }
+
+    #[test]
+    fn test_find_manifests_outside_of_sparse_checkout() {
+        let root = fixtures().join("workspace");
+        // crates/gone is in the commit, but not on the disk
+        let read = |path: &Path| {
+            let file =
+                crate::path_in_repository("", &path.strip_prefix(&root).unwrap().to_string_lossy());
+            if file == "crates/gone/Cargo.toml" {
+                return Ok(String::from(
+                    "[package]\nname = \"gone\"\nrust-version.workspace = true\n",
+                ));
+            }
+            read_from_disk(&root.join(file))
+        };
+        let files = strings(&[
+            "Cargo.toml",
+            "crates/foo/Cargo.toml",
+            "crates/gone/Cargo.toml",
+        ]);
+
+        let manifests = find_manifests(&root, &files, &read);
+        let mut names = manifests.keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["foo", "gone"]);
+        assert_eq!(manifests["gone"].rust_version, "1.74");
+        assert_eq!(
+            load_workspace(&root, &files, &read)
+                .unwrap()
+                .unwrap()
+                .members,
+            strings(&["foo", "gone"])
+        );
+    }
+
+    #[test]
+    fn test_load_workspace() {
+        let root = fixtures().join("workspace");
+        let files = strings(&[
+            "Cargo.toml",
+            "crates/bar/Cargo.toml",
+            "crates/foo/Cargo.toml",
+            "crates/old/Cargo.toml",
+        ]);
+        assert_eq!(
+            load_workspace(&root, &files, &read_from_disk).unwrap(),
+            Some(Workspace {
+                virtual_manifest: true,
+                members: strings(&["bar", "foo"]),
+                lints: true,
+                dependencies: 2,
+            })
+        );
+
+        let root = fixtures().join("workspace-root-package");
+        assert_eq!(
+            load_workspace(&root, &strings(&["Cargo.toml"]), &read_from_disk).unwrap(),
+            Some(Workspace {
+                virtual_manifest: false,
+                members: strings(&["root"]),
+                lints: false,
+                dependencies: 0,
+            })
+        );
+
+        let root = fixtures().join("simple");
+        assert_eq!(
+            load_workspace(&root, &strings(&["Cargo.toml"]), &read_from_disk),
+            Ok(None)
+        );
+        load_workspace(&fixtures(), &[], &read_from_disk).unwrap_err();
+    }
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("crates/*", "crates/foo"));
+        assert!(matches_glob("crates/foo-*", "crates/foo-bar"));
+        assert!(matches_glob("crates/fo?", "crates/foo"));
+        assert!(!matches_glob("crates/*", "crates/foo/bar"));
+        assert!(!matches_glob("crates/*", "crates"));
+        assert!(!matches_glob("crates/foo-*", "crates/bar"));
+        assert!(matches_glob("foo", "foo"));
+    }
+
+    #[test]
+    fn test_find_mismatches() {
+        let mut krate = Crate::new();
+        krate.repository = "https://github.com/foo/full".to_owned();
+        krate.documentation = "https://docs.rs/full/".to_owned();
+        krate.description = "A crate with all\n the fields we collect".to_owned();
+
+        let root = fixtures().join("full");
+        let manifest = load_manifest(&root, &root).unwrap();
+        assert_eq!(find_mismatches(&krate, "1.0.0", Some(&manifest)), vec![]);
+        assert_eq!(
+            find_mismatches(&krate, "1.0.0", None),
+            vec![Mismatch::NameNotFound]
+        );
+
+        krate.repository = "https://github.com/bar/full".to_owned();
+        krate.homepage = "https://full.org/".to_owned();
+        krate.description = "Old description".to_owned();
+        assert_eq!(
+            find_mismatches(&krate, "1.0.10", Some(&manifest)),
+            vec![
+                Mismatch::Repository,
+                Mismatch::Homepage,
+                Mismatch::Description,
+                Mismatch::VersionLower
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.2.3"), Some(vec![1, 2, 3]));
+        assert_eq!(parse_version("1.2.3-alpha.1+build"), Some(vec![1, 2, 3]));
+        assert_eq!(parse_version(""), None);
+        assert!(parse_version("0.9.10") > parse_version("0.9.9"));
+    }
+
+    #[test]
+    fn test_load_manifest_package_in_workspace_root() {
+        let root = fixtures().join("workspace-root-package");
+        let manifest = load_manifest(&root, &root).unwrap();
+        assert_eq!(manifest.edition, "2021");
+        assert_eq!(manifest.rust_version, "1.65");
+    }
+}
@@ -0,0 +1,472 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use tar::Archive;
+
+use crate::vcs_backend::Vcs;
+use crate::Details;
+
+pub const DEFAULT_CRATES_URL: &str = "https://static.crates.io/crates";
+
+/// Value of `Details::source` when we analyzed the published package and not the repository.
+pub const SOURCE_PACKAGE: &str = "published package";
+
+/// Value of `Details::source` when we analyzed the clone of the repository.
+pub const SOURCE_REPOSITORY: &str = "repository";
+
+/// Files that cargo adds to the package, we don't expect to find them in the repository.
+const GENERATED_FILES: &[&str] = &[".cargo_vcs_info.json", "Cargo.toml.orig", "Cargo.lock"];
+
+/// The content of the `.cargo_vcs_info.json` file that `cargo package` adds if the crate is in a git repository.
+#[derive(Debug, Default, serde::Deserialize, PartialEq, Eq)]
+pub struct CargoVcsInfo {
+    #[serde(default)]
+    pub git: CargoVcsInfoGit,
+
+    #[serde(default)]
+    pub path_in_vcs: String,
+}
+
+#[derive(Debug, Default, serde::Deserialize, PartialEq, Eq)]
+pub struct CargoVcsInfoGit {
+    #[serde(default)]
+    pub dirty: bool,
+
+    #[serde(default)]
+    pub sha1: String,
+}
+
+/// The unpacked published packages, parallel to the tree of cloned repositories.
+pub fn get_packages_folder() -> PathBuf {
+    PathBuf::from("packages")
+}
+
+/// Where to find the .crate file of a specific version.
+/// `base` is either the URL of a server (e.g. static.crates.io) or a local folder with the same layout.
+pub fn get_crate_file_location(base: &str, name: &str, version: &str) -> String {
+    format!(
+        "{}/{name}/{name}-{version}.crate",
+        base.trim_end_matches('/')
+    )
+}
+
+/// Download (or read from a local folder) the .crate file of the given version
+/// and unpack it into the `name` folder inside `root`, replacing the previous version.
+///
+/// # Errors
+///
+/// Will return `Err` if we cannot fetch the .crate file or if it is not a proper .crate file.
+pub fn fetch_package(
+    root: &Path,
+    base: &str,
+    name: &str,
+    version: &str,
+) -> Result<PathBuf, String> {
+    let location = get_crate_file_location(base, name, version);
+    log::info!("fetch package from {location}");
+    let content = read_crate_file(&location)?;
+
+    let path = root.join(name);
+    if path.exists() {
+        fs::remove_dir_all(&path)
+            .map_err(|err| format!("Could not remove {}: {err}", path.display()))?;
+    }
+    fs::create_dir_all(&path)
+        .map_err(|err| format!("Could not create {}: {err}", path.display()))?;
+    unpack_crate_file(&content, &path)?;
+    Ok(path)
+}
+
+fn read_crate_file(location: &str) -> Result<Vec<u8>, String> {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        let response = reqwest::blocking::get(location)
+            .map_err(|err| format!("Could not get '{location}': {err}"))?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "Could not get '{location}': status {}",
+                response.status()
+            ));
+        }
+        let bytes = response
+            .bytes()
+            .map_err(|err| format!("Could not read '{location}': {err}"))?;
+        return Ok(bytes.to_vec());
+    }
+
+    fs::read(location).map_err(|err| format!("Could not read '{location}': {err}"))
+}
+
+/// A .crate file is a gzipped tarball with all the files in a `name-version/` folder.
+/// We unpack the content of that folder into `path`.
+fn unpack_crate_file(content: &[u8], path: &Path) -> Result<(), String> {
+    let mut archive = Archive::new(GzDecoder::new(content));
+    let entries = archive
+        .entries()
+        .map_err(|err| format!("Invalid .crate file: {err}"))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|err| format!("Invalid .crate file: {err}"))?;
+        let entry_path = entry
+            .path()
+            .map_err(|err| format!("Invalid path in .crate file: {err}"))?
+            .into_owned();
+
+        // skip the top folder and refuse anything that would end up outside of `path`
+        let mut components = entry_path.components();
+        components.next();
+        let relative = components.as_path();
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            log::warn!("Skipping suspicious path in .crate file: {entry_path:?}");
+            continue;
+        }
+
+        // a symlink would let the next entries escape from `path`, cargo does not package them anyway
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            log::warn!("Skipping {entry_type:?} entry in .crate file: {entry_path:?}");
+            continue;
+        }
+
+        let target = path.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| format!("Could not create {}: {err}", parent.display()))?;
+        }
+        entry
+            .unpack(&target)
+            .map_err(|err| format!("Could not unpack {}: {err}", target.display()))?;
+    }
+    Ok(())
+}
+
+/// Compare the unpacked package in `package` with the clone of its repository in `repo`
+/// and save the discrepancies in the details.
+pub fn compare_with_repository(
+    details: &mut Details,
+    package: &Path,
+    repo: &Path,
+    vcs: Vcs,
+    name: &str,
+    timeout: u64,
+) {
+    let vcs_info = read_cargo_vcs_info(package).unwrap_or_default();
+
+    // compare with the files in the commit and not on the disk as a sparse clone has only some of them
+    details.package_only_files = match vcs.files(repo, timeout) {
+        Ok(repo_files) => files_not_in_repository(package, &repo_files, &vcs_info.path_in_vcs),
+        Err(err) => {
+            log::error!(
+                "Could not list the files in {}: {}",
+                repo.display(),
+                err.as_str()
+            );
+            vec![]
+        }
+    };
+
+    let tags = vcs.tags(repo, timeout).unwrap_or_default();
+    details.package_version_tag =
+        find_version_tag(&tags, name, &details.package_version).unwrap_or_default();
+
+    details.package_vcs_commit = vcs_info.git.sha1;
+    details.package_vcs_commit_in_repo = !details.package_vcs_commit.is_empty()
+        && vcs
+            .has_commit(repo, &details.package_vcs_commit, timeout)
+            .unwrap_or(true); // if we could not check, we don't report it
+}
+
+/// `None` if the package has no `.cargo_vcs_info.json` file, e.g. because it was not published from a git repository.
+pub fn read_cargo_vcs_info(package: &Path) -> Option<CargoVcsInfo> {
+    let content = fs::read_to_string(package.join(".cargo_vcs_info.json")).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(vcs_info) => Some(vcs_info),
+        Err(err) => {
+            log::error!(
+                "Invalid .cargo_vcs_info.json in {}: {err}",
+                package.display()
+            );
+            None
+        }
+    }
+}
+
+/// The files of the package that we could not find in the files of the repository (`repo_files`).
+///
+/// We look for them in the folder of the crate (`path_in_vcs`) and in the root of the repository,
+/// as cargo copies e.g. the README of the workspace into the package.
+pub fn files_not_in_repository(
+    package: &Path,
+    repo_files: &[String],
+    path_in_vcs: &str,
+) -> Vec<String> {
+    let repo_files = repo_files
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<&str>>();
+    let prefix = path_in_vcs.trim_end_matches('/');
+    package_files(package)
+        .into_iter()
+        .filter(|file| !GENERATED_FILES.contains(&file.as_str()))
+        .filter(|file| {
+            !repo_files.contains(file.as_str())
+                && (prefix.is_empty() || !repo_files.contains(format!("{prefix}/{file}").as_str()))
+        })
+        .collect()
+}
+
+/// The files of the unpacked `package` relative to its folder, sorted.
+pub fn package_files(package: &Path) -> Vec<String> {
+    let mut files = vec![];
+    list_files(package, Path::new(""), &mut files);
+    files.sort();
+    files
+}
+
+fn list_files(root: &Path, relative: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(root.join(relative)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = relative.join(entry.file_name());
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => list_files(root, &path, files),
+            Ok(_) => files.push(path.to_string_lossy().to_string()),
+            Err(_) => {}
+        }
+    }
+}
+
+/// The tag that marks the given version of the crate. We accept tags such as
+/// `1.2.3`, `v1.2.3`, `name-1.2.3`, `name-v1.2.3`, `name@1.2.3` and `name/v1.2.3`.
+pub fn find_version_tag(tags: &[String], name: &str, version: &str) -> Option<String> {
+    tags.iter()
+        .find(|tag| is_version_tag(tag, name, version))
+        .cloned()
+}
+
+fn is_version_tag(tag: &str, name: &str, version: &str) -> bool {
+    let Some(prefix) = tag.strip_suffix(version) else {
+        return false;
+    };
+    let prefix = prefix
+        .strip_suffix(['v', 'V'])
+        .unwrap_or(prefix)
+        .trim_end_matches(['-', '_', '@', '/']);
+    prefix.is_empty() || prefix.replace('_', "-") == name.replace('_', "-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_folder;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn create_crate_file(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for &(name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_get_crate_file_location() {
+        assert_eq!(
+            get_crate_file_location(DEFAULT_CRATES_URL, "serde", "1.0.0"),
+            "https://static.crates.io/crates/serde/serde-1.0.0.crate"
+        );
+        assert_eq!(
+            get_crate_file_location("/tmp/crates/", "serde", "1.0.0"),
+            "/tmp/crates/serde/serde-1.0.0.crate"
+        );
+    }
+
+    #[test]
+    fn test_find_version_tag() {
+        let tags = |tags: &[&str]| tags.iter().map(|&tag| tag.to_owned()).collect::<Vec<_>>();
+        for tag in [
+            "1.2.3",
+            "v1.2.3",
+            "foo-1.2.3",
+            "foo-v1.2.3",
+            "foo@1.2.3",
+            "foo/v1.2.3",
+        ] {
+            assert_eq!(
+                find_version_tag(&tags(&["0.1.0", tag]), "foo", "1.2.3"),
+                Some(tag.to_owned())
+            );
+        }
+        assert_eq!(
+            find_version_tag(&tags(&["foo_bar-v1.2.3"]), "foo-bar", "1.2.3"),
+            Some("foo_bar-v1.2.3".to_owned())
+        );
+        assert_eq!(
+            find_version_tag(&tags(&["11.2.3", "v0.1.2.3", "bar-1.2.3"]), "foo", "1.2.3"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_files_not_in_repository() {
+        let root = temp_folder("package-compare");
+        let package = root.join("package");
+        for file in [
+            "Cargo.toml",
+            "Cargo.toml.orig",
+            "README.md",
+            "src/lib.rs",
+            "src/secret.rs",
+        ] {
+            fs::create_dir_all(package.join(file).parent().unwrap()).unwrap();
+            fs::write(package.join(file), "").unwrap();
+        }
+        // the files of the commit, a sparse clone might have only some of them on the disk
+        assert_eq!(
+            package_files(&package),
+            vec![
+                "Cargo.toml",
+                "Cargo.toml.orig",
+                "README.md",
+                "src/lib.rs",
+                "src/secret.rs",
+            ]
+        );
+        let repo_files = [
+            "README.md",
+            "crates/foo/Cargo.toml",
+            "crates/foo/src/lib.rs",
+        ]
+        .map(str::to_owned);
+
+        assert_eq!(
+            files_not_in_repository(&package, &repo_files, "crates/foo"),
+            vec!["src/secret.rs"]
+        );
+        assert_eq!(
+            files_not_in_repository(&package, &repo_files, "crates/foo/"),
+            vec!["src/secret.rs"]
+        );
+        assert_eq!(
+            files_not_in_repository(&package, &repo_files, ""),
+            vec!["Cargo.toml", "src/lib.rs", "src/secret.rs"]
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_read_cargo_vcs_info() {
+        let root = temp_folder("package-vcs-info");
+        assert_eq!(read_cargo_vcs_info(&root), None);
+
+        fs::write(
+            root.join(".cargo_vcs_info.json"),
+            r#"{"git": {"sha1": "abc123"}, "path_in_vcs": "crates/foo"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            read_cargo_vcs_info(&root),
+            Some(CargoVcsInfo {
+                git: CargoVcsInfoGit {
+                    sha1: "abc123".to_owned(),
+                    dirty: false,
+                },
+                path_in_vcs: "crates/foo".to_owned(),
+            })
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_fetch_package_from_local_folder() {
+        let root = temp_folder("package");
+        let base = root.join("crates");
+        fs::create_dir_all(base.join("foo")).unwrap();
+        fs::write(
+            base.join("foo").join("foo-0.1.0.crate"),
+            create_crate_file(&[
+                ("foo-0.1.0/Cargo.toml", "[package]\nname = \"foo\"\n"),
+                ("foo-0.1.0/src/lib.rs", "\n"),
+            ]),
+        )
+        .unwrap();
+        fs::write(
+            base.join("foo").join("foo-0.2.0.crate"),
+            create_crate_file(&[("foo-0.2.0/Cargo.toml", "[package]\nname = \"foo\"\n")]),
+        )
+        .unwrap();
+
+        let packages = root.join("packages");
+        let base = base.to_str().unwrap();
+        let path = fetch_package(&packages, base, "foo", "0.1.0").unwrap();
+        assert_eq!(path, packages.join("foo"));
+        assert_eq!(
+            fs::read_to_string(path.join("Cargo.toml")).unwrap(),
+            "[package]\nname = \"foo\"\n"
+        );
+        assert!(path.join("src").join("lib.rs").exists());
+
+        // a new version replaces the old one
+        let path = fetch_package(&packages, base, "foo", "0.2.0").unwrap();
+        assert!(path.join("Cargo.toml").exists());
+        assert!(!path.join("src").exists());
+
+        fetch_package(&packages, base, "foo", "0.3.0").unwrap_err();
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_unpack_crate_file_skips_links() {
+        let root = temp_folder("package");
+        let outside = root.join("outside");
+        fs::create_dir_all(&outside).unwrap();
+
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, entry_type) in [
+            ("foo-0.1.0/a", tar::EntryType::Symlink),
+            ("foo-0.1.0/b", tar::EntryType::Link),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_size(0);
+            header.set_mode(0o777);
+            builder.append_link(&mut header, name, &outside).unwrap();
+        }
+        for name in ["foo-0.1.0/a/x", "foo-0.1.0/b"] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, &b"evil"[..])
+                .unwrap();
+        }
+        let content = builder.into_inner().unwrap().finish().unwrap();
+
+        let package = root.join("package");
+        unpack_crate_file(&content, &package).unwrap();
+        assert!(!outside.join("x").exists());
+        assert!(!package.join("a").is_symlink());
+        assert_eq!(
+            fs::read_to_string(package.join("a").join("x")).unwrap(),
+            "evil"
+        );
+        assert_eq!(fs::read_to_string(package.join("b")).unwrap(), "evil");
+        assert_eq!(fs::read_dir(&outside).unwrap().count(), 0);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,106 @@
+mod macros;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use macros::ok_or_exit;
+use rust_digger::package::get_packages_folder;
+use rust_digger::{
+    find_orphans, get_details_path, get_owner_and_repo, get_package_details_path,
+    get_package_settings_path, get_repos_folder, get_settings_path, read_crates, remove_orphan,
+    repo_details_root, Crate,
+};
+
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Cli {
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Delete the clones and details that no crate refers to. Without this we only list them."
+    )]
+    delete: bool,
+}
+
+/// The clones, packages and details we expect to have based on the current list of crates.
+struct Expected {
+    /// The details and the settings of the repositories.
+    details: HashSet<PathBuf>,
+    package_details: HashSet<PathBuf>,
+    packages: HashSet<PathBuf>,
+    repos: HashSet<PathBuf>,
+}
+
+/// Find the clones in `repos/`, the unpacked packages in `packages/` and the files in `repo-details/`
+/// that do not belong to any of the crates, e.g. because the crate changed its repository or was deleted.
+fn main() {
+    let args = Cli::parse();
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    log::info!("Starting the prune process");
+
+    let crates: Vec<Crate> = ok_or_exit!(read_crates(0), 2);
+    let expected = collect_expected(&crates);
+
+    let details_root = repo_details_root();
+    let package_details_root = details_root.join("crates-io");
+    let trees = [
+        (get_repos_folder(), 3, &expected.repos),
+        (details_root, 3, &expected.details),
+        (get_packages_folder(), 1, &expected.packages),
+        (package_details_root, 1, &expected.package_details),
+    ];
+
+    let mut count = 0;
+    for (root, depth, expected) in trees {
+        for orphan in find_orphans(&root, depth, expected) {
+            count += 1;
+            if args.delete {
+                log::info!("Removing {}", orphan.display());
+                if let Err(err) = remove_orphan(&root, &orphan) {
+                    log::error!("{err}");
+                }
+            } else {
+                log::info!("Orphan {}", orphan.display());
+            }
+        }
+    }
+
+    log::info!("Found {count} orphans");
+    log::info!("Ending the prune process");
+}
+
+fn collect_expected(crates: &[Crate]) -> Expected {
+    let mut expected = Expected {
+        repos: HashSet::new(),
+        details: HashSet::new(),
+        packages: HashSet::new(),
+        package_details: HashSet::new(),
+    };
+
+    for krate in crates {
+        expected
+            .packages
+            .insert(get_packages_folder().join(&krate.name));
+        expected
+            .package_details
+            .insert(get_package_details_path(&krate.name));
+        expected
+            .package_details
+            .insert(get_package_settings_path(&krate.name));
+
+        if let Some(details_path) = get_details_path(&krate.repository) {
+            expected.details.insert(details_path);
+            expected
+                .details
+                .extend(get_settings_path(&krate.repository));
+            let (host, owner, repo) = get_owner_and_repo(&krate.repository);
+            expected
+                .repos
+                .insert(get_repos_folder().join(host).join(owner).join(repo));
+        }
+    }
+
+    expected
+}
@@ -0,0 +1,555 @@
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::manifest::ManifestReadme;
+use crate::path_in_repository;
+
+/// Parts of the URLs of the badge images. The first matching entry wins, so e.g. the downloads
+/// badge of shields.io is not counted as a Crates.io version badge.
+const BADGE_PATTERNS: &[(Badge, &[&str])] = &[
+    (Badge::DocsRs, &["docs.rs", "/docsrs/"]),
+    (Badge::Coverage, &["codecov", "coveralls", "coverage"]),
+    (
+        Badge::Downloads,
+        &["/crates/d/", "/crates/dv/", "/crates/dr/", "download"],
+    ),
+    (Badge::License, &["/crates/l/", "license"]),
+    (
+        Badge::Msrv,
+        &["/crates/msrv/", "msrv", "rust-version", "rustc"],
+    ),
+    (
+        Badge::Dependencies,
+        &["deps.rs", "dependencies", "dependency-status"],
+    ),
+    (
+        Badge::Ci,
+        &[
+            "/workflows/",
+            "/workflow/",
+            "/actions",
+            "pipeline",
+            "travis-ci",
+            "circleci",
+            "appveyor",
+            "cirrus-ci",
+            "builds.sr.ht",
+            "/build",
+        ],
+    ),
+    (Badge::CratesIo, &["/crates/v/", "crates.io", "/crates/"]),
+];
+
+/// An image is a badge if its URL contains one of these.
+const BADGE_HOSTS: &[&str] = &[
+    "shields.io",
+    "badgen.net",
+    "badge",
+    "codecov.io",
+    "coveralls.io",
+    "deps.rs",
+    "travis-ci",
+    "circleci.com",
+    "appveyor.com",
+    "/workflows/",
+];
+
+/// Headings of the sections that explain how to add the crate to a project.
+const INSTALL_HEADINGS: &[&str] = &["install", "getting started", "setup", "set up"];
+
+/// Snippets that explain how to add the crate to a project even without a section.
+const INSTALL_SNIPPETS: &[&str] = &["cargo add ", "cargo install ", "[dependencies]"];
+
+/// Headings of the sections that explain how to use the crate.
+const USAGE_HEADINGS: &[&str] = &[
+    "usage",
+    "example",
+    "quick start",
+    "quickstart",
+    "getting started",
+    "how to use",
+    "tutorial",
+];
+
+/// The attributes of the code blocks rustdoc understands, e.g. `rust,no_run`.
+const RUST_CODE_ATTRIBUTES: &[&str] = &[
+    "rust",
+    "rs",
+    "no_run",
+    "ignore",
+    "should_panic",
+    "compile_fail",
+    "edition2015",
+    "edition2018",
+    "edition2021",
+    "edition2024",
+];
+
+/// The kinds of badges shown in a README.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Badge {
+    Ci,
+    Coverage,
+    CratesIo,
+    Dependencies,
+    DocsRs,
+    Downloads,
+    License,
+    Msrv,
+    Other,
+}
+
+impl Badge {
+    pub const ALL: [Self; 9] = [
+        Self::CratesIo,
+        Self::DocsRs,
+        Self::Ci,
+        Self::Coverage,
+        Self::License,
+        Self::Downloads,
+        Self::Msrv,
+        Self::Dependencies,
+        Self::Other,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CratesIo => "crates-io",
+            Self::DocsRs => "docs-rs",
+            Self::Ci => "ci",
+            Self::Coverage => "coverage",
+            Self::License => "license",
+            Self::Downloads => "downloads",
+            Self::Msrv => "msrv",
+            Self::Dependencies => "dependencies",
+            Self::Other => "other",
+        }
+    }
+
+    /// The kind of the badge with the image at `url`, or `None` if the image does not look like a badge.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let url = url.to_lowercase();
+        if !BADGE_HOSTS.iter().any(|host| url.contains(host)) {
+            return None;
+        }
+        Some(
+            BADGE_PATTERNS
+                .iter()
+                .find(|entry| entry.1.iter().any(|pattern| url.contains(pattern)))
+                .map_or(Self::Other, |entry| entry.0),
+        )
+    }
+}
+
+/// What we found in the README file of a crate.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Readme {
+    pub badges: Vec<Badge>,
+    /// The path of the file relative to the root of the repository.
+    pub file: String,
+    /// `markdown`, `asciidoc`, `restructuredtext`, `org`, `html` or `text`.
+    pub format: String,
+    /// There is a code block with Rust code.
+    pub has_code_example: bool,
+    /// There is an installation section or a `cargo add` command or a `[dependencies]` snippet.
+    pub has_install: bool,
+    /// There is a usage or examples section.
+    pub has_usage: bool,
+    pub headings: Vec<String>,
+    /// The size of the file in bytes.
+    pub size: usize,
+}
+
+impl Readme {
+    /// Analyze the `content` of the README `file`.
+    pub fn parse(file: &str, content: &str) -> Self {
+        let format = format_of(file);
+        let headings = headings(content, format == "asciidoc");
+        let has_heading = |names: &[&str]| {
+            headings.iter().any(|heading| {
+                let heading = heading.to_lowercase();
+                names.iter().any(|name| heading.contains(name))
+            })
+        };
+        let has_install = has_heading(INSTALL_HEADINGS)
+            || INSTALL_SNIPPETS
+                .iter()
+                .any(|snippet| content.contains(snippet));
+        let has_usage = has_heading(USAGE_HEADINGS);
+
+        let mut badges = image_urls(content)
+            .iter()
+            .filter_map(|url| Badge::from_url(url))
+            .collect::<Vec<_>>();
+        badges.sort_by_key(|badge| badge.as_str());
+        badges.dedup();
+
+        Self {
+            file: file.to_owned(),
+            format: format.to_owned(),
+            size: content.len(),
+            headings,
+            has_install,
+            has_usage,
+            has_code_example: has_code_example(content),
+            badges,
+        }
+    }
+}
+
+/// The format of a README file based on its extension.
+fn format_of(file: &str) -> &'static str {
+    let extension = Path::new(file)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "md" | "markdown" | "mdown" | "mkd" => "markdown",
+        "adoc" | "asciidoc" | "asc" => "asciidoc",
+        "rst" => "restructuredtext",
+        "org" => "org",
+        "html" | "htm" => "html",
+        _ => "text",
+    }
+}
+
+/// Remove the HTML tags, the Markdown emphasis and the closing `#` characters around a heading.
+fn clean_heading(text: &str) -> String {
+    static TAG: Lazy<Regex> = Lazy::new(|| Regex::new("<[^>]*>").unwrap());
+    TAG.replace_all(text, "")
+        .trim()
+        .trim_end_matches('#')
+        .trim()
+        .trim_matches(|char| char == '*' || char == '_' || char == '`')
+        .trim()
+        .to_owned()
+}
+
+/// The headings outside the code blocks.
+///
+/// We recognize the Markdown (`# Title` and underlined), Asciidoc (`= Title`), RST (underlined)
+/// and HTML (`<h1>`) headings.
+fn headings(content: &str, asciidoc: bool) -> Vec<String> {
+    static HTML_HEADING: Lazy<Regex> =
+        Lazy::new(|| Regex::new("(?is)<h[1-6][^>]*>(.*?)</h[1-6]>").unwrap());
+
+    let mut headings = vec![];
+    let mut in_code = false;
+    let mut previous = "";
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```")
+            || trimmed.starts_with("~~~")
+            || (asciidoc && trimmed == "----")
+        {
+            in_code = !in_code;
+            previous = "";
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let marker = trimmed.chars().next().unwrap_or(' ');
+        let title = trimmed.trim_start_matches(marker);
+        if (marker == '#' || (asciidoc && marker == '='))
+            && !line.starts_with(' ')
+            && title.starts_with(' ')
+            && trimmed.len() - title.len() <= 6
+        {
+            headings.push(clean_heading(title));
+            previous = "";
+        } else if !previous.is_empty()
+            && trimmed.len() >= 3
+            && matches!(marker, '=' | '-' | '~' | '^')
+            && trimmed.chars().all(|char| char == marker)
+        {
+            headings.push(clean_heading(previous));
+            previous = "";
+        } else {
+            previous = trimmed;
+        }
+    }
+
+    headings.extend(
+        HTML_HEADING
+            .captures_iter(content)
+            .map(|captures| clean_heading(&captures[1])),
+    );
+    headings.retain(|heading| !heading.is_empty());
+    headings
+}
+
+/// Is there a code block with Rust code in a Markdown, Asciidoc or RST file?
+///
+/// A Markdown code block without a language counts if it looks like Rust code.
+fn has_code_example(content: &str) -> bool {
+    let lowercase = content.to_lowercase();
+    if [
+        "[source,rust",
+        "[source, rust",
+        "code-block:: rust",
+        "code:: rust",
+    ]
+    .iter()
+    .any(|marker| lowercase.contains(marker))
+    {
+        return true;
+    }
+
+    let mut info = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        let fence = trimmed
+            .strip_prefix("```")
+            .or_else(|| trimmed.strip_prefix("~~~"));
+        match (info, fence) {
+            (None, Some(attributes)) => {
+                let attributes = attributes.trim_start_matches(['`', '~']).trim();
+                if attributes
+                    .split([',', ' '])
+                    .any(|attribute| RUST_CODE_ATTRIBUTES.contains(&attribute))
+                {
+                    return true;
+                }
+                info = Some(attributes);
+            }
+            (Some(_), Some(_)) => info = None,
+            (Some(""), None) => {
+                if ["fn ", "use ", "let "]
+                    .iter()
+                    .any(|keyword| trimmed.starts_with(keyword))
+                {
+                    return true;
+                }
+            }
+            (Some(_) | None, None) => {}
+        }
+    }
+    false
+}
+
+/// The URLs of the images in Markdown (inline and reference) and HTML.
+fn image_urls(content: &str) -> Vec<String> {
+    static INLINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!\[[^\]]*\]\(\s*<?([^)\s>]+)").unwrap());
+    static REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!\[[^\]]*\]\[([^\]]*)\]").unwrap());
+    static DEFINITION: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?m)^\s*\[([^\]]+)\]:\s*<?(\S+?)>?(\s.*)?$").unwrap());
+    static HTML: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"(?i)<img[^>]*\ssrc\s*=\s*["']([^"']+)["']"#).unwrap());
+    static ASCIIDOC: Lazy<Regex> = Lazy::new(|| Regex::new(r"image:+(\S+?)\[").unwrap());
+    static RST: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)\.\. image:: (\S+)").unwrap());
+
+    let references = REFERENCE
+        .captures_iter(content)
+        .map(|captures| captures[1].to_lowercase())
+        .collect::<Vec<_>>();
+    let mut urls = INLINE
+        .captures_iter(content)
+        .chain(HTML.captures_iter(content))
+        .chain(ASCIIDOC.captures_iter(content))
+        .chain(RST.captures_iter(content))
+        .map(|captures| captures[1].to_owned())
+        .collect::<Vec<_>>();
+    urls.extend(
+        DEFINITION
+            .captures_iter(content)
+            .filter(|captures| references.contains(&captures[1].to_lowercase()))
+            .map(|captures| captures[2].to_owned()),
+    );
+    urls
+}
+
+/// The path of the `readme` field of the `Cargo.toml` in `crate_dir`, relative to the root of the repository.
+///
+/// `None` if the field is missing or `false`.
+pub fn manifest_readme_path(crate_dir: &str, readme: &ManifestReadme) -> Option<String> {
+    match readme.clone() {
+        ManifestReadme::Path(path) => Some(path_in_repository(crate_dir, &path)),
+        ManifestReadme::Disabled | ManifestReadme::Unset => None,
+    }
+}
+
+/// The README file of the crate in `crate_dir` among the `files` of the repository.
+///
+/// The `readme` field of the `Cargo.toml` comes first, then a README file next to the `Cargo.toml`
+/// and then a README file in the root of the repository.
+pub fn find_readme(files: &[String], crate_dir: &str, readme: &ManifestReadme) -> Option<String> {
+    if let Some(path) = manifest_readme_path(crate_dir, readme) {
+        if files.contains(&path) {
+            return Some(path);
+        }
+    }
+
+    let find_in = |dir: &str| {
+        files
+            .iter()
+            .filter(|file| {
+                let name = if dir.is_empty() {
+                    Some(file.as_str())
+                } else {
+                    file.strip_prefix(dir)
+                        .and_then(|name| name.strip_prefix('/'))
+                };
+                name.is_some_and(|name| {
+                    !name.contains('/')
+                        && name
+                            .get(..6)
+                            .is_some_and(|start| start.eq_ignore_ascii_case("readme"))
+                })
+            })
+            .min_by_key(|file| (file.len(), file.as_str()))
+            .cloned()
+    };
+    let crate_dir = crate_dir.trim_end_matches('/');
+    find_in(crate_dir).or_else(|| find_in(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_from_url() {
+        let cases = [
+            (
+                "https://img.shields.io/crates/v/serde.svg",
+                Some(Badge::CratesIo),
+            ),
+            (
+                "https://img.shields.io/crates/d/serde.svg",
+                Some(Badge::Downloads),
+            ),
+            (
+                "https://img.shields.io/crates/l/serde.svg",
+                Some(Badge::License),
+            ),
+            ("https://docs.rs/serde/badge.svg", Some(Badge::DocsRs)),
+            (
+                "https://github.com/foo/bar/actions/workflows/ci.yml/badge.svg",
+                Some(Badge::Ci),
+            ),
+            (
+                "https://codecov.io/gh/foo/bar/branch/main/graph/badge.svg",
+                Some(Badge::Coverage),
+            ),
+            (
+                "https://deps.rs/repo/github/foo/bar/status.svg",
+                Some(Badge::Dependencies),
+            ),
+            (
+                "https://img.shields.io/badge/rustc-1.70+-lightgray.svg",
+                Some(Badge::Msrv),
+            ),
+            ("https://img.shields.io/discord/123", Some(Badge::Other)),
+            ("https://example.com/screenshot.png", None),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(Badge::from_url(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let content = r#"<h1 align="center">Foo</h1>
+
+[![Crates.io](https://img.shields.io/crates/v/foo.svg)](https://crates.io/crates/foo)
+[![docs][docs-badge]][docs]
+<img src="https://github.com/foo/foo/actions/workflows/ci.yml/badge.svg">
+![screenshot](docs/screenshot.png)
+
+[docs-badge]: https://docs.rs/foo/badge.svg
+[docs]: https://docs.rs/foo
+
+## Installation
+
+```toml
+[dependencies]
+foo = "1.0"
+```
+
+Usage
+-----
+
+```
+# comment in a shell block
+use foo::bar;
+```
+"#;
+        let readme = Readme::parse("crates/foo/README.md", content);
+        assert_eq!(readme.format, "markdown");
+        assert_eq!(readme.size, content.len());
+        assert_eq!(readme.headings, vec!["Installation", "Usage", "Foo"]);
+        assert!(readme.has_install);
+        assert!(readme.has_usage);
+        assert!(readme.has_code_example);
+        assert_eq!(
+            readme.badges,
+            vec![Badge::Ci, Badge::CratesIo, Badge::DocsRs]
+        );
+
+        let readme = Readme::parse("README", "Foo\n===\n\nA library.\n\n    cargo build\n");
+        assert_eq!(readme.format, "text");
+        assert_eq!(readme.headings, vec!["Foo"]);
+        assert!(!readme.has_install);
+        assert!(!readme.has_usage);
+        assert!(!readme.has_code_example);
+        assert_eq!(readme.badges, vec![]);
+
+        let readme = Readme::parse(
+            "README.adoc",
+            "= Foo\n\n== Examples\n\n[source,rust]\n----\nfn main() {}\n----\n",
+        );
+        assert_eq!(readme.format, "asciidoc");
+        assert_eq!(readme.headings, vec!["Foo", "Examples"]);
+        assert!(readme.has_usage);
+        assert!(readme.has_code_example);
+    }
+
+    #[test]
+    fn test_find_readme() {
+        let path = |path: &str| ManifestReadme::Path(path.to_owned());
+        let files = [
+            "README.md",
+            "README.ja.md",
+            "crates/foo/Cargo.toml",
+            "crates/foo/readme.md",
+            "crates/bar/Cargo.toml",
+            "docs/README.md",
+        ]
+        .iter()
+        .map(|&file| file.to_owned())
+        .collect::<Vec<_>>();
+        assert_eq!(
+            find_readme(&files, "", &ManifestReadme::Unset),
+            Some("README.md".to_owned())
+        );
+        assert_eq!(
+            find_readme(&files, "crates/foo", &ManifestReadme::Disabled),
+            Some("crates/foo/readme.md".to_owned())
+        );
+        assert_eq!(
+            find_readme(&files, "crates/bar", &path("../../docs/README.md")),
+            Some("docs/README.md".to_owned())
+        );
+        // the file in the manifest is missing
+        assert_eq!(
+            find_readme(&files, "crates/bar", &path("README.md")),
+            Some("README.md".to_owned())
+        );
+        assert_eq!(find_readme(&[], "", &ManifestReadme::Unset), None);
+
+        assert_eq!(
+            manifest_readme_path("crates/bar", &path("../../README.md")),
+            Some("README.md".to_owned())
+        );
+        assert_eq!(
+            manifest_readme_path("", &path("./README.md")),
+            Some("README.md".to_owned())
+        );
+        assert_eq!(manifest_readme_path("", &ManifestReadme::Disabled), None);
+        assert_eq!(manifest_readme_path("", &ManifestReadme::Unset), None);
+    }
+}
@@ -1,22 +1,76 @@
 use chrono::prelude::{DateTime, Utc};
+use core::hash::{Hash as _, Hasher as _};
 use liquid_filter_commafy::Commafy;
 use rust_digger::build_path;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs;
 use std::fs::File;
+use std::hash::DefaultHasher;
 use std::io::Read;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use once_cell::sync::Lazy;
-use regex::Regex;
-
-use crate::{collected_data_root, Crate, CratesByOwner, Partials, Repo, User, PAGE_SIZE, VERSION};
-use rust_digger::{get_owner_and_repo, percentage};
+use crate::{Crate, CratesByOwner, Partials, Repo, User, PAGE_SIZE, VERSION};
+use rust_digger::ci::{split_action, ActionWarning, CiCheck, CiProvider, Pinning};
+use rust_digger::config::{load_settings, ConfigTool, Setting};
+use rust_digger::license::LicenseProblem;
+use rust_digger::manifest::Mismatch;
+use rust_digger::readme::Badge;
+use rust_digger::{
+    get_owner_and_repo, get_package_settings_path, get_repo_types, get_settings_path, percentage,
+    Details, RepoState,
+};
 
 const URL: &str = "https://rust-digger.code-maven.com";
 
+/// A README shorter than this many bytes hardly says more than the description.
+const SHORT_README: u32 = 500;
+
+/// The longest part of a page name we take from a setting. The pages of the values are called `{key}_{value}` and the
+/// file systems allow 255 bytes.
+const MAX_PAGE_NAME: usize = 100;
+
+/// The tools with settings saved next to the details of the repositories: the name of the tool, which is also the
+/// folder of the pages, the key in the stats, the title and the description.
+const SETTINGS_PAGES: [(&str, &str, &str, &str); 3] = [
+    (
+        "rustfmt",
+        "config_rustfmt",
+        "Rustfmt configuration",
+        "The options set in the rustfmt.toml or .rustfmt.toml file.",
+    ),
+    (
+        "clippy",
+        "config_clippy",
+        "Clippy configuration",
+        "The options set in the clippy.toml or .clippy.toml file.",
+    ),
+    (
+        "lints",
+        "config_lints",
+        "Lints in Cargo.toml",
+        "The level of each lint in the [lints.rust], [lints.clippy] and [lints.rustdoc] tables of the Cargo.toml file, including the ones inherited from the workspace.",
+    ),
+];
+
+type ManifestPage = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&Details) -> bool,
+);
+
+/// A tool with an index page and lists of the crates by each setting: the folder of the pages, the key in the stats,
+/// the title, the description and the settings of a crate as `(key, value)` pairs where the value might be empty.
+type ConfigPages = (
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&Details) -> Vec<(String, String)>,
+);
+
 fn get_site_folder() -> PathBuf {
     PathBuf::from("_site")
 }
@@ -341,7 +395,12 @@ fn generate_list_of_users(users: &Vec<User>) {
     log::info!("generate_list_of_users end");
 }
 
-fn render_stats_page(crates: usize, stats: &HashMap<&str, usize>) {
+fn render_stats_page(
+    crates: usize,
+    stats: &HashMap<&str, usize>,
+    ci: &[liquid::Object],
+    workflows: &[liquid::Object],
+) {
     log::info!("render_stats_page");
     let partials = load_templates().unwrap();
 
@@ -370,6 +429,8 @@ fn render_stats_page(crates: usize, stats: &HashMap<&str, usize>) {
         "total": crates,
         "percentage": perc,
         "stats": stats,
+        "ci": ci,
+        "workflows": workflows,
     });
     let html = template.render(&globals).unwrap();
     let mut file = File::create(filename).unwrap();
@@ -378,7 +439,19 @@ fn render_stats_page(crates: usize, stats: &HashMap<&str, usize>) {
 
 pub fn create_folders() {
     let _res = fs::create_dir_all(get_site_folder());
-    for folder in ["crates", "users", "news", "vcs", "rustfmt"] {
+    for folder in [
+        "crates",
+        "users",
+        "news",
+        "vcs",
+        "rustfmt",
+        "toolchain",
+        "clippy",
+        "deny",
+        "cargo-config",
+        "lints",
+        "lint-attributes",
+    ] {
         let _res = fs::create_dir_all(get_site_folder().join(folder));
     }
 }
@@ -462,6 +535,13 @@ fn collect_repos(crates: &[Crate]) -> Result<usize, Box<dyn Error>> {
         },
     )?;
 
+    let moved_count = render_filtered_crates(
+        "vcs/repository-moved",
+        "Crates whose repository moved",
+        crates,
+        |krate| !krate.details.redirected_url.is_empty(),
+    )?;
+
     repos = repos
         .into_iter()
         .map(|mut repo| {
@@ -490,6 +570,16 @@ fn collect_repos(crates: &[Crate]) -> Result<usize, Box<dyn Error>> {
         bold: true,
     });
 
+    repos.push(Repo {
+        display: String::from("Repository moved"),
+        name: String::from("repository-moved"),
+        url: String::new(),
+        count: moved_count,
+        percentage: percentage(moved_count, crates.len()),
+        platform: None,
+        bold: true,
+    });
+
     repos.push(Repo {
         display: String::from("Has no repository"),
         name: String::from("no-repo"),
@@ -600,7 +690,7 @@ pub fn generate_pages(crates: &[Crate]) -> Result<(), Box<dyn Error>> {
         |krate| crate_has_no_owner(krate),
     )?;
 
-    let stats = HashMap::from([
+    let mut stats = HashMap::from([
         ("crates_without_owner", crates_without_owner),
         ("crates_without_owner_name", crates_without_owner_name),
         ("home_page_but_no_repo", home_page_but_no_repo),
@@ -614,9 +704,20 @@ pub fn generate_pages(crates: &[Crate]) -> Result<(), Box<dyn Error>> {
         ("has_cargo_toml_in_root", has_cargo_toml_in_root),
         ("has_no_cargo_toml_in_root", has_no_cargo_toml_in_root),
     ]);
-
-    render_stats_page(crates.len(), &stats);
-    generate_rustfmt_pages(crates.len(), &stats, crates)?;
+    generate_package_pages(crates, &mut stats)?;
+    generate_repo_state_pages(crates, &mut stats)?;
+    generate_manifest_pages(crates, &mut stats)?;
+    generate_mismatch_pages(crates, &mut stats)?;
+    generate_workspace_pages(crates, &mut stats)?;
+    let ci = generate_ci_pages(crates, &mut stats)?;
+    let workflows = generate_workflow_pages(crates, &mut stats)?;
+    generate_action_pages(crates, &mut stats)?;
+    generate_update_bot_pages(crates, &mut stats)?;
+    generate_license_pages(crates, &mut stats)?;
+    generate_readme_pages(crates, &mut stats)?;
+    generate_config_pages(crates, &mut stats)?;
+
+    render_stats_page(crates.len(), &stats, &ci, &workflows);
     generate_msrv_pages(crates)?;
 
     Ok(())
@@ -641,6 +742,968 @@ fn render_filtered_crates(
     Ok(filtered_crates.len())
 }
 
+/// The discrepancies between the published packages and their repositories.
+fn generate_package_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let package_files_not_in_repo = render_filtered_crates(
+        "package-files-not-in-repo",
+        "Published package has files that are not in the repository",
+        crates,
+        |krate| compared_with_repo(krate) && !krate.package_details.package_only_files.is_empty(),
+    )?;
+    stats.insert("package_files_not_in_repo", package_files_not_in_repo);
+
+    let package_version_without_tag = render_filtered_crates(
+        "package-version-without-tag",
+        "Published version has no tag in the repository",
+        crates,
+        |krate| compared_with_repo(krate) && krate.package_details.package_version_tag.is_empty(),
+    )?;
+    stats.insert("package_version_without_tag", package_version_without_tag);
+
+    let package_commit_not_in_repo = render_filtered_crates(
+        "package-commit-not-in-repo",
+        "Published commit is not in the repository",
+        crates,
+        |krate| {
+            compared_with_repo(krate)
+                && !krate.package_details.package_vcs_commit.is_empty()
+                && !krate.package_details.package_vcs_commit_in_repo
+        },
+    )?;
+    stats.insert("package_commit_not_in_repo", package_commit_not_in_repo);
+
+    Ok(())
+}
+
+/// A list of the crates for each problematic state of the repository we found in the vcs stage.
+fn generate_repo_state_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    for state in RepoState::ALL {
+        let (key, title) = match state {
+            RepoState::Empty => ("repo_empty", "The repository is empty"),
+            RepoState::DefaultBranchMissing => (
+                "repo_default_branch_missing",
+                "The default branch of the repository is missing",
+            ),
+            RepoState::NoRustFiles => ("repo_no_rust_files", "The repository has no Rust files"),
+            RepoState::NoCargoToml => (
+                "repo_no_cargo_toml",
+                "The repository has no Cargo.toml file",
+            ),
+            RepoState::Ok | _ => continue,
+        };
+        let count = render_filtered_crates(
+            &format!("repo-{}", state.as_str()),
+            title,
+            crates,
+            |krate| krate.details.repo_state == state.as_str(),
+        )?;
+        stats.insert(key, count);
+    }
+    Ok(())
+}
+
+/// Lists of the crates based on the fields of their `Cargo.toml` in the repository.
+fn generate_manifest_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let pages: [ManifestPage; 8] = [
+        (
+            "manifest-no-license",
+            "manifest_no_license",
+            "Cargo.toml has neither license nor license-file",
+            |details| details.license.is_empty() && details.license_file.is_empty(),
+        ),
+        (
+            "manifest-no-categories-no-keywords",
+            "manifest_no_categories_no_keywords",
+            "Cargo.toml has neither categories nor keywords",
+            |details| details.categories.is_empty() && details.keywords.is_empty(),
+        ),
+        (
+            "manifest-publish-false",
+            "manifest_publish_false",
+            "Cargo.toml says the crate should not be published",
+            |details| !details.publish,
+        ),
+        (
+            "manifest-proc-macro",
+            "manifest_proc_macro",
+            "Procedural macro crates",
+            |details| details.targets.iter().any(|target| target == "proc-macro"),
+        ),
+        (
+            "manifest-lib-and-bin",
+            "manifest_lib_and_bin",
+            "Crates with both a library and a binary",
+            |details| {
+                details.targets.iter().any(|target| target == "lib")
+                    && details.targets.iter().any(|target| target == "bin")
+            },
+        ),
+        (
+            "manifest-build-script",
+            "manifest_build_script",
+            "Crates with a build script",
+            |details| details.build_script,
+        ),
+        (
+            "manifest-links",
+            "manifest_links",
+            "Crates linking to a native library",
+            |details| !details.links.is_empty(),
+        ),
+        (
+            "manifest-badges",
+            "manifest_badges",
+            "Cargo.toml has badges",
+            |details| !details.badges.is_empty(),
+        ),
+    ];
+
+    for (filename, key, title, cond) in pages {
+        let count = render_filtered_crates(filename, title, crates, |krate| {
+            !krate.details.manifest_name.is_empty() && cond(&krate.details)
+        })?;
+        stats.insert(key, count);
+    }
+    Ok(())
+}
+
+/// A list of the crates for each kind of difference between Crates.io and the `Cargo.toml` in the repository.
+fn generate_mismatch_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    for mismatch in Mismatch::ALL {
+        let (key, title) = match mismatch {
+            Mismatch::NameNotFound => (
+                "mismatch_name_not_found",
+                "The repository has no package with the name of the crate",
+            ),
+            Mismatch::Repository => (
+                "mismatch_repository",
+                "The repository field in the repository is different",
+            ),
+            Mismatch::Homepage => (
+                "mismatch_homepage",
+                "The homepage field in the repository is different",
+            ),
+            Mismatch::Documentation => (
+                "mismatch_documentation",
+                "The documentation field in the repository is different",
+            ),
+            Mismatch::Description => (
+                "mismatch_description",
+                "The description field in the repository is different",
+            ),
+            Mismatch::VersionLower => (
+                "mismatch_version_lower",
+                "The version in the repository is lower than the published one",
+            ),
+            _ => continue,
+        };
+        let count = render_filtered_crates(
+            &format!("mismatch-{}", mismatch.as_str()),
+            title,
+            crates,
+            |krate| {
+                krate
+                    .details
+                    .manifest_mismatches
+                    .get(&krate.name)
+                    .is_some_and(|kinds| kinds.iter().any(|kind| kind == mismatch.as_str()))
+            },
+        )?;
+        stats.insert(key, count);
+    }
+    Ok(())
+}
+
+/// Lists of the crates based on the workspace in the root of their repository
+/// and a page grouping the crates that are published from the same workspace.
+fn generate_workspace_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let pages: [ManifestPage; 4] = [
+        (
+            "workspace",
+            "workspace",
+            "Crates in a workspace",
+            |_details| true,
+        ),
+        (
+            "workspace-virtual",
+            "workspace_virtual",
+            "Crates in a workspace with a virtual manifest",
+            |details| details.workspace_virtual,
+        ),
+        (
+            "workspace-lints",
+            "workspace_lints",
+            "Crates in a workspace with shared lints",
+            |details| details.workspace_lints,
+        ),
+        (
+            "workspace-dependencies",
+            "workspace_dependencies",
+            "Crates in a workspace with shared dependencies",
+            |details| 0 < details.workspace_dependencies,
+        ),
+    ];
+
+    let in_workspace = |krate: &Crate| krate.details.workspace_crates.contains(&krate.name);
+    for (filename, key, title, cond) in pages {
+        let count = render_filtered_crates(filename, title, crates, |krate| {
+            in_workspace(krate) && cond(&krate.details)
+        })?;
+        stats.insert(key, count);
+    }
+
+    // keyed by (host, owner, repo) as the URLs of the members might point to their folders
+    let mut workspaces: HashMap<(String, String, String), Vec<&Crate>> = HashMap::new();
+    for krate in crates.iter().filter(|krate| in_workspace(krate)) {
+        workspaces
+            .entry(get_owner_and_repo(&krate.repository))
+            .or_default()
+            .push(krate);
+    }
+    let mut workspaces = workspaces
+        .into_values()
+        .filter(|members| 1 < members.len())
+        .collect::<Vec<_>>();
+    workspaces.sort_by_cached_key(|members| {
+        (
+            core::cmp::Reverse(members.len()),
+            members[0].repository.to_lowercase(),
+        )
+    });
+    let workspaces = workspaces
+        .iter()
+        .map(|members| {
+            let details = &members[0].details;
+            liquid::object!({
+                "repository": members[0].repository,
+                "members": details.workspace_members.len(),
+                "virtual": details.workspace_virtual,
+                "lints": details.workspace_lints,
+                "dependencies": details.workspace_dependencies,
+                "crates": members.iter().map(|krate| krate.name.clone()).collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let template = liquid::ParserBuilder::with_stdlib()
+        .filter(Commafy)
+        .partials(load_templates()?)
+        .build()?
+        .parse_file("templates/workspaces.html")?;
+    let utc: DateTime<Utc> = Utc::now();
+    let globals = liquid::object!({
+        "version": format!("{VERSION}"),
+        "utc":     format!("{}", utc),
+        "title":   "Crates published from the same workspace",
+        "workspaces": workspaces,
+    });
+    let html = template.render(&globals)?;
+    let mut file = File::create(get_site_folder().join("workspaces.html"))?;
+    writeln!(&mut file, "{html}")?;
+
+    Ok(())
+}
+
+/// A list of the crates for each CI system and the rows of the CI table of the stats page.
+fn generate_ci_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<Vec<liquid::Object>, Box<dyn Error>> {
+    let mut providers = vec![];
+    for provider in CiProvider::ALL {
+        let count = render_filtered_crates(
+            &format!("ci-{}", provider.as_str()),
+            &format!("Crates using {}", provider.display()),
+            crates,
+            |krate| krate.details.ci.iter().any(|ci| ci == provider.as_str()),
+        )?;
+        providers.push((provider, count));
+    }
+    providers.sort_by_key(|&(_, count)| core::cmp::Reverse(count));
+
+    let several = render_filtered_crates(
+        "ci-several",
+        "Crates using more than one CI system",
+        crates,
+        |krate| 1 < krate.details.ci.len(),
+    )?;
+    stats.insert("ci_several", several);
+
+    let none = render_filtered_crates(
+        "ci-none",
+        "The repository has no CI configured",
+        crates,
+        |krate| !krate.details.analysed_head.is_empty() && krate.details.ci.is_empty(),
+    )?;
+    stats.insert("ci_none", none);
+
+    Ok(providers
+        .into_iter()
+        .map(|(provider, count)| {
+            liquid::object!({
+                "name": provider.as_str(),
+                "display": provider.display(),
+                "count": count,
+                "percentage": percentage(count, crates.len()),
+            })
+        })
+        .collect())
+}
+
+/// Lists of the crates based on what their GitHub Actions workflows run
+/// and the rows of the workflows table of the stats page.
+fn generate_workflow_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<Vec<liquid::Object>, Box<dyn Error>> {
+    let has_actions = |krate: &Crate| {
+        krate
+            .details
+            .ci
+            .iter()
+            .any(|ci| ci == CiProvider::GitHubActions.as_str())
+    };
+    let runs = |krate: &Crate, check: CiCheck| {
+        krate
+            .details
+            .ci_checks
+            .iter()
+            .any(|name| name == check.as_str())
+    };
+
+    let mut rows = vec![];
+    for check in CiCheck::ALL {
+        let page = format!("ci-runs-{}", check.as_str());
+        let count = render_filtered_crates(
+            &page,
+            &format!("GitHub Actions run {}", check.display()),
+            crates,
+            |krate| runs(krate, check),
+        )?;
+        rows.push((page, format!("Runs {}", check.display()), count));
+    }
+    for (os, display) in [
+        ("linux", "Linux"),
+        ("windows", "Windows"),
+        ("macos", "macOS"),
+    ] {
+        let page = format!("ci-os-{os}");
+        let count = render_filtered_crates(
+            &page,
+            &format!("GitHub Actions run on {display}"),
+            crates,
+            |krate| krate.details.ci_os.iter().any(|name| name == os),
+        )?;
+        rows.push((page, format!("Runs on {display}"), count));
+    }
+
+    let without_tests = render_filtered_crates(
+        "ci-without-tests",
+        "GitHub Actions without tests",
+        crates,
+        |krate| has_actions(krate) && !runs(krate, CiCheck::Test),
+    )?;
+    stats.insert("ci_without_tests", without_tests);
+
+    let without_clippy = render_filtered_crates(
+        "ci-without-clippy",
+        "GitHub Actions without clippy",
+        crates,
+        |krate| has_actions(krate) && !runs(krate, CiCheck::Clippy),
+    )?;
+    stats.insert("ci_without_clippy", without_clippy);
+
+    let scheduled = render_filtered_crates(
+        "ci-scheduled",
+        "GitHub Actions running on a schedule",
+        crates,
+        |krate| !krate.details.ci_cron.is_empty(),
+    )?;
+    stats.insert("ci_scheduled", scheduled);
+
+    Ok(rows
+        .into_iter()
+        .map(|(page, display, count)| {
+            liquid::object!({
+                "page": page,
+                "display": display,
+                "count": count,
+                "percentage": percentage(count, crates.len()),
+            })
+        })
+        .collect())
+}
+
+/// A list of the crates for each kind of problem with the actions used in their workflows
+/// and a page with the number of crates using each action.
+fn generate_action_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    for warning in ActionWarning::ALL {
+        let (key, title) = match warning {
+            ActionWarning::Branch => (
+                "ci_actions_branch",
+                "GitHub Actions using an action from a branch",
+            ),
+            ActionWarning::Deprecated => (
+                "ci_actions_deprecated",
+                "GitHub Actions using a deprecated action",
+            ),
+            ActionWarning::Outdated => (
+                "ci_actions_outdated",
+                "GitHub Actions using an old version of an official action",
+            ),
+            _ => continue,
+        };
+        let count = render_filtered_crates(
+            &format!("ci-actions-{}", warning.as_str()),
+            title,
+            crates,
+            |krate| {
+                krate
+                    .details
+                    .ci_action_warnings
+                    .values()
+                    .any(|warnings| warnings.iter().any(|name| name == warning.as_str()))
+            },
+        )?;
+        stats.insert(key, count);
+    }
+
+    // the number of crates using each action, in total and by the way it is pinned
+    let mut usage: HashMap<String, HashMap<&str, usize>> = HashMap::new();
+    for krate in crates {
+        let mut seen = vec![];
+        for action in &krate.details.ci_actions {
+            let (name, version) = split_action(action);
+            let name = name.to_lowercase();
+            let counts = usage.entry(name.clone()).or_default();
+            *counts
+                .entry(Pinning::from_version(version).as_str())
+                .or_insert(0) += 1;
+            if !seen.contains(&name) {
+                *counts.entry("crates").or_insert(0) += 1;
+                seen.push(name);
+            }
+        }
+    }
+    let mut usage = usage
+        .into_iter()
+        .filter(|entry| 1 < entry.1["crates"])
+        .collect::<Vec<_>>();
+    usage.sort_by_cached_key(|entry| (core::cmp::Reverse(entry.1["crates"]), entry.0.clone()));
+    let actions = usage
+        .into_iter()
+        .map(|(name, counts)| {
+            let count = |key: &str| counts.get(key).copied().unwrap_or_default();
+            liquid::object!({
+                "name": name,
+                "crates": count("crates"),
+                "sha": count(Pinning::Sha.as_str()),
+                "tag": count(Pinning::Tag.as_str()),
+                "branch": count(Pinning::Branch.as_str()),
+                "deprecated": ActionWarning::from_action(&name).contains(&ActionWarning::Deprecated),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let template = liquid::ParserBuilder::with_stdlib()
+        .filter(Commafy)
+        .partials(load_templates()?)
+        .build()?
+        .parse_file("templates/actions.html")?;
+    let utc: DateTime<Utc> = Utc::now();
+    let globals = liquid::object!({
+        "version": format!("{VERSION}"),
+        "utc":     format!("{}", utc),
+        "title":   "GitHub Actions usage",
+        "actions": actions,
+    });
+    let html = template.render(&globals)?;
+    let mut file = File::create(get_site_folder().join("actions.html"))?;
+    writeln!(&mut file, "{html}")?;
+
+    Ok(())
+}
+
+/// Lists of the crates based on the bots that keep their dependencies fresh.
+fn generate_update_bot_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let pages: [ManifestPage; 6] = [
+        (
+            "update-bot-dependabot",
+            "update_bot_dependabot",
+            "Dependencies updated by Dependabot",
+            |details| details.update_bots.iter().any(|bot| bot == "dependabot"),
+        ),
+        (
+            "update-bot-renovate",
+            "update_bot_renovate",
+            "Dependencies updated by Renovate",
+            |details| details.update_bots.iter().any(|bot| bot == "renovate"),
+        ),
+        (
+            "update-bot-cargo",
+            "update_bot_cargo",
+            "A bot keeps the cargo dependencies fresh",
+            |details| details.update_ecosystems.iter().any(|name| name == "cargo"),
+        ),
+        (
+            "update-bot-github-actions",
+            "update_bot_github_actions",
+            "A bot keeps the GitHub Actions fresh",
+            |details| {
+                details
+                    .update_ecosystems
+                    .iter()
+                    .any(|name| name == "github-actions")
+            },
+        ),
+        (
+            "update-bot-without-cargo",
+            "update_bot_without_cargo",
+            "There is a dependency update bot, but it does not update the cargo dependencies",
+            |details| {
+                !details.update_bots.is_empty()
+                    && !details.update_ecosystems.iter().any(|name| name == "cargo")
+            },
+        ),
+        (
+            "update-bot-none",
+            "update_bot_none",
+            "There is no dependency update bot",
+            |details| !details.analysed_head.is_empty() && details.update_bots.is_empty(),
+        ),
+    ];
+
+    for (filename, key, title, cond) in pages {
+        let count = render_filtered_crates(filename, title, crates, |krate| cond(&krate.details))?;
+        stats.insert(key, count);
+    }
+    Ok(())
+}
+
+/// A list of the crates for each kind of problem with their license files.
+fn generate_license_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    for problem in LicenseProblem::ALL {
+        let (key, title) = match problem {
+            LicenseProblem::MissingFile => {
+                ("license_missing_file", "Crates without a license file")
+            }
+            LicenseProblem::Mismatch => (
+                "license_mismatch",
+                "The license file does not match the license in Cargo.toml",
+            ),
+            LicenseProblem::MissingDualFile => (
+                "license_missing_dual_file",
+                "Multiple licenses, but not all of them have a license file",
+            ),
+            LicenseProblem::PublishedMismatch => (
+                "license_published_mismatch",
+                "The license in Cargo.toml differs from the license on Crates.io",
+            ),
+            _ => continue,
+        };
+        let count = render_filtered_crates(
+            &format!("license-{}", problem.as_str()),
+            title,
+            crates,
+            |krate| {
+                krate
+                    .details
+                    .license_problems
+                    .iter()
+                    .any(|name| name == problem.as_str())
+            },
+        )?;
+        stats.insert(key, count);
+    }
+    Ok(())
+}
+
+/// Lists of the crates by what their README files contain and by the kinds of badges they show.
+fn generate_readme_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let pages: [ManifestPage; 7] = [
+        (
+            "readme-none",
+            "readme_none",
+            "Crates without a README file",
+            |details| !details.analysed_head.is_empty() && details.readme_file.is_empty(),
+        ),
+        (
+            "readme-missing",
+            "readme_missing",
+            "The readme file in Cargo.toml does not exist",
+            |details| details.readme_missing,
+        ),
+        ("readme-short", "readme_short", "Short README", |details| {
+            !details.readme_file.is_empty() && details.readme_size < SHORT_README
+        }),
+        (
+            "readme-without-install",
+            "readme_without_install",
+            "The README does not explain how to install the crate",
+            |details| !details.readme_file.is_empty() && !details.readme_has_install,
+        ),
+        (
+            "readme-without-usage",
+            "readme_without_usage",
+            "The README has no usage or examples section",
+            |details| !details.readme_file.is_empty() && !details.readme_has_usage,
+        ),
+        (
+            "readme-without-code-example",
+            "readme_without_code_example",
+            "The README has no Rust code example",
+            |details| !details.readme_file.is_empty() && !details.readme_has_code_example,
+        ),
+        (
+            "readme-without-badges",
+            "readme_without_badges",
+            "The README has no badges",
+            |details| !details.readme_file.is_empty() && details.readme_badges.is_empty(),
+        ),
+    ];
+    for (filename, key, title, cond) in pages {
+        let count = render_filtered_crates(filename, title, crates, |krate| cond(&krate.details))?;
+        stats.insert(key, count);
+    }
+
+    for badge in Badge::ALL {
+        let (key, title) = match badge {
+            Badge::CratesIo => ("readme_badge_crates_io", "README with a Crates.io badge"),
+            Badge::DocsRs => ("readme_badge_docs_rs", "README with a docs.rs badge"),
+            Badge::Ci => ("readme_badge_ci", "README with a CI badge"),
+            Badge::Coverage => ("readme_badge_coverage", "README with a coverage badge"),
+            Badge::License => ("readme_badge_license", "README with a license badge"),
+            Badge::Downloads => ("readme_badge_downloads", "README with a downloads badge"),
+            Badge::Msrv => ("readme_badge_msrv", "README with an MSRV badge"),
+            Badge::Dependencies => (
+                "readme_badge_dependencies",
+                "README with a dependency status badge",
+            ),
+            Badge::Other => ("readme_badge_other", "README with some other badge"),
+            _ => continue,
+        };
+        let count = render_filtered_crates(
+            &format!("readme-badge-{}", badge.as_str()),
+            title,
+            crates,
+            |krate| {
+                krate
+                    .details
+                    .readme_badges
+                    .iter()
+                    .any(|name| name == badge.as_str())
+            },
+        )?;
+        stats.insert(key, count);
+    }
+    Ok(())
+}
+
+/// The configuration file of `tool` as a setting called `file`.
+fn config_file(details: &Details, tool: ConfigTool) -> Vec<(String, String)> {
+    details
+        .config_files
+        .iter()
+        .filter(|file| tool.files().contains(&file.as_str()))
+        .map(|file| (String::from("file"), file.clone()))
+        .collect()
+}
+
+fn config_keys(keys: &[String]) -> Vec<(String, String)> {
+    keys.iter()
+        .map(|key| (key.clone(), String::new()))
+        .collect()
+}
+
+/// The name of the page of `text` in a file name, e.g. `clippy::pedantic` becomes `clippy--pedantic-` and a hash.
+///
+/// The characters that do not belong in a file name are replaced and the long texts are cut. In both cases we add
+/// the hash of the text, so e.g. `1.70` and `1-70` get different pages.
+fn page_name(text: &str) -> String {
+    let name = text
+        .chars()
+        .take(MAX_PAGE_NAME)
+        .map(|char| {
+            if char.is_ascii_alphanumeric() || char == '_' || char == '-' {
+                char
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    if name == text {
+        return name;
+    }
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!("{name}-{:016x}", hasher.finish())
+}
+
+/// The tools with configuration pages.
+fn config_tools() -> [ConfigPages; 4] {
+    [
+        (
+            "toolchain",
+            "config_toolchain",
+            "Rust toolchain",
+            "The channel, the components and the targets in the rust-toolchain.toml or rust-toolchain file.",
+            |details| {
+                let mut settings = config_file(details, ConfigTool::Toolchain);
+                if !details.toolchain_channel.is_empty() {
+                    settings.push((
+                        String::from("channel"),
+                        details.toolchain_channel.clone(),
+                    ));
+                }
+                for component in &details.toolchain_components {
+                    settings.push((String::from("component"), component.clone()));
+                }
+                for target in &details.toolchain_targets {
+                    settings.push((String::from("target"), target.clone()));
+                }
+                settings
+            },
+        ),
+        (
+            "deny",
+            "config_deny",
+            "cargo-deny configuration",
+            "The sections of the deny.toml file of cargo-deny.",
+            |details| {
+                let mut settings = config_file(details, ConfigTool::Deny);
+                settings.extend(config_keys(&details.deny_config));
+                settings
+            },
+        ),
+        (
+            "cargo-config",
+            "config_cargo",
+            "Cargo configuration",
+            "The tables and keys in the .cargo/config.toml or .cargo/config file.",
+            |details| {
+                let mut settings = config_file(details, ConfigTool::CargoConfig);
+                settings.extend(config_keys(&details.cargo_config));
+                settings
+            },
+        ),
+        (
+            "lint-attributes",
+            "config_lint_attributes",
+            "Lint attributes",
+            "The lints in the crate-level warn, deny and forbid attributes of src/lib.rs, e.g. #![forbid(unsafe_code)].",
+            |details| {
+                details
+                    .lint_attributes
+                    .iter()
+                    .filter_map(|attribute| {
+                        let (level, lint) = attribute.trim_end_matches(')').split_once('(')?;
+                        Some((level.to_owned(), lint.to_owned()))
+                    })
+                    .collect()
+            },
+        ),
+    ]
+}
+
+/// The toolchain, the configuration of rustfmt, clippy, cargo-deny and cargo, and the lints the crates enable.
+fn generate_config_pages(
+    crates: &[Crate],
+    stats: &mut HashMap<&str, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let template = liquid::ParserBuilder::with_stdlib()
+        .filter(Commafy)
+        .partials(load_templates()?)
+        .build()?
+        .parse_file("templates/config.html")?;
+
+    for (folder, key, title, description, settings_of) in config_tools() {
+        let settings = crates
+            .iter()
+            .map(|krate| (krate, settings_of(&krate.details)))
+            .filter(|entry| !entry.1.is_empty())
+            .collect::<Vec<_>>();
+        render_config_pages((folder, title, description), &settings, crates, &template)?;
+        stats.insert(key, settings.len());
+    }
+
+    let collected = load_collected_settings(crates);
+    for (tool, key, title, description) in SETTINGS_PAGES {
+        let mut by_crate: HashMap<&str, Vec<(String, String)>> = HashMap::new();
+        for setting in collected.iter().filter(|setting| setting.tool == tool) {
+            // the lists and tables, e.g. the `ignore` of rustfmt, only count for the key
+            let value = if setting.is_scalar() {
+                setting.display_value()
+            } else {
+                String::new()
+            };
+            by_crate
+                .entry(&setting.krate)
+                .or_default()
+                .push((setting.key.clone(), value));
+        }
+        let settings = crates
+            .iter()
+            .filter_map(|krate| Some((krate, by_crate.remove(krate.name.as_str())?)))
+            .collect::<Vec<_>>();
+        render_config_pages((tool, title, description), &settings, crates, &template)?;
+        stats.insert(key, settings.len());
+    }
+    Ok(())
+}
+
+/// The settings saved by `rust-digger-vcs` next to the details of the repositories and the packages of the `crates`.
+fn load_collected_settings(crates: &[Crate]) -> Vec<Setting> {
+    let mut paths = crates
+        .iter()
+        .filter_map(|krate| get_settings_path(&krate.repository))
+        .chain(
+            crates
+                .iter()
+                .map(|krate| get_package_settings_path(&krate.name)),
+        )
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .iter()
+        .flat_map(|path| {
+            load_settings(path).unwrap_or_else(|err| {
+                log::error!("{err}");
+                vec![]
+            })
+        })
+        .collect()
+}
+
+/// Render the index page of a tool and the lists of the crates by each of the `settings`.
+fn render_config_pages(
+    (folder, title, description): (&str, &str, &str),
+    settings: &[(&Crate, Vec<(String, String)>)],
+    crates: &[Crate],
+    template: &liquid::Template,
+) -> Result<(), Box<dyn Error>> {
+    let mut count_by_key: HashMap<&str, usize> = HashMap::new();
+    let mut count_by_pair: HashMap<(&str, &str), usize> = HashMap::new();
+    for entry in settings {
+        for setting in &entry.1 {
+            *count_by_key.entry(&setting.0).or_insert(0) += 1;
+            if !setting.1.is_empty() {
+                *count_by_pair.entry((&setting.0, &setting.1)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut count_by_key = count_by_key.into_iter().collect::<Vec<_>>();
+    count_by_key.sort_by_key(|entry| (core::cmp::Reverse(entry.1), entry.0));
+    for entry in &count_by_key {
+        let crate_names = settings
+            .iter()
+            .filter(|krate| krate.1.iter().any(|setting| setting.0 == entry.0))
+            .map(|krate| &krate.0.name)
+            .collect::<Vec<_>>();
+        render_filtered_crates(
+            &format!("{folder}/{}", page_name(entry.0)),
+            &format!("{title}: {}", entry.0),
+            crates,
+            |krate| crate_names.contains(&&krate.name),
+        )?;
+    }
+
+    let mut count_by_pair = count_by_pair.into_iter().collect::<Vec<_>>();
+    count_by_pair.sort_by_key(|entry| (entry.0 .0, core::cmp::Reverse(entry.1), entry.0 .1));
+    for entry in &count_by_pair {
+        let crate_names = settings
+            .iter()
+            .filter(|krate| {
+                krate
+                    .1
+                    .iter()
+                    .any(|setting| setting.0 == entry.0 .0 && setting.1 == entry.0 .1)
+            })
+            .map(|krate| &krate.0.name)
+            .collect::<Vec<_>>();
+        render_filtered_crates(
+            &format!(
+                "{folder}/{}_{}",
+                page_name(entry.0 .0),
+                page_name(entry.0 .1)
+            ),
+            &format!("{title}: {} set to {}", entry.0 .0, entry.0 .1),
+            crates,
+            |krate| crate_names.contains(&&krate.name),
+        )?;
+    }
+
+    let utc: DateTime<Utc> = Utc::now();
+    let globals = liquid::object!({
+        "version": format!("{VERSION}"),
+        "utc":     format!("{}", utc),
+        "title":   title,
+        "description": description,
+        "folder": folder,
+        "number_of_crates": crates.len(),
+        "with_config": settings.len(),
+        "count_by_key": count_by_key
+            .iter()
+            .map(|entry| {
+                liquid::object!({
+                    "key": entry.0,
+                    "page": page_name(entry.0),
+                    "count": entry.1,
+                })
+            })
+            .collect::<Vec<_>>(),
+        "count_by_pair": count_by_pair
+            .iter()
+            .map(|entry| {
+                liquid::object!({
+                    "key": entry.0 .0,
+                    "value": entry.0 .1,
+                    "page": format!("{}_{}", page_name(entry.0 .0), page_name(entry.0 .1)),
+                    "count": entry.1,
+                })
+            })
+            .collect::<Vec<_>>(),
+    });
+    let html = template.render(&globals)?;
+    let mut file = File::create(get_site_folder().join(folder).join("index.html"))?;
+    writeln!(&mut file, "{html}")?;
+    Ok(())
+}
+
+const fn compared_with_repo(krate: &Crate) -> bool {
+    !krate.package_details.package_compared_head.is_empty()
+}
+
 fn no_homepage_no_repo(krate: &Crate) -> bool {
     krate.homepage.is_empty() && krate.repository.is_empty()
 }
@@ -666,7 +1729,7 @@ fn on_github_but_no_ci(krate: &Crate) -> bool {
         return false;
     }
 
-    if krate.details.has_github_action {
+    if !krate.details.ci.is_empty() {
         return false;
     }
 
@@ -687,7 +1750,7 @@ fn on_gitlab_but_no_ci(krate: &Crate) -> bool {
         return false;
     }
 
-    if krate.details.has_gitlab_pipeline {
+    if !krate.details.ci.is_empty() {
         return false;
     }
 
@@ -702,44 +1765,6 @@ fn crate_has_no_owner(krate: &Crate) -> bool {
     krate.owner_name.is_empty() && krate.owner_gh_login.is_empty()
 }
 
-fn get_repo_types() -> Vec<Repo> {
-    let text = include_str!("../repo_types.yaml");
-
-    let repos: Vec<Repo> = serde_yaml::from_str(text).unwrap();
-    repos
-}
-
-fn load_collected_rustfmt() -> Vec<(String, String, String)> {
-    let mut rustfmt: Vec<(String, String, String)> = vec![];
-
-    let filename = collected_data_root().join("rustfmt.txt");
-    match std::fs::read_to_string(&filename) {
-        Err(err) => {
-            log::error!("Could not read {:?} {err}", filename);
-        }
-        Ok(content) => {
-            for row in content.split('\n') {
-                if row.is_empty() {
-                    continue;
-                }
-                log::info!("{row}");
-                let parts = row.split(',').collect::<Vec<&str>>();
-                if parts.len() != 3 {
-                    log::error!("Row '{row}' was split to {} parts", parts.len());
-                    continue;
-                }
-                rustfmt.push((
-                    parts[0].to_owned(),
-                    parts[1].to_owned(),
-                    parts[2].to_owned(),
-                ));
-            }
-        }
-    }
-
-    rustfmt
-}
-
 fn generate_msrv_pages(crates: &[Crate]) -> Result<(), Box<dyn Error>> {
     let mut editions: HashMap<String, u32> = HashMap::new();
     let mut rust_versions: HashMap<String, u32> = HashMap::new();
@@ -830,133 +1855,15 @@ fn generate_msrv_pages(crates: &[Crate]) -> Result<(), Box<dyn Error>> {
     for rust_version in rust_versions {
         render_filtered_crates(
             &format!("rust-version-{}", rust_version.1),
-            &format!("Crates with rust_version field being '{}'", rust_version.0),
+            &format!("Crates with rust-version field being '{}'", rust_version.0),
             crates,
-            |krate| krate.details.edition == rust_version.0,
+            |krate| krate.details.rust_version == rust_version.0,
         )?;
     }
 
     Ok(())
 }
 
-fn generate_rustfmt_pages(
-    number_of_crates: usize,
-    stats: &HashMap<&str, usize>,
-    crates: &[Crate],
-) -> Result<(), Box<dyn Error>> {
-    static RE_KEY: Lazy<Regex> = Lazy::new(|| Regex::new("^[a-z_]+$").unwrap());
-    static RE_VALUE: Lazy<Regex> = Lazy::new(|| Regex::new("^[0-9A-Za-z_]+$").unwrap());
-
-    let rustfmt = load_collected_rustfmt();
-    let mut count_by_key: HashMap<String, u32> = HashMap::new();
-    let mut count_by_pair: HashMap<(String, String), u32> = HashMap::new();
-
-    #[allow(clippy::explicit_iter_loop)] // TODO
-    #[allow(clippy::pattern_type_mismatch)] // TODO
-    for (key, value, _krate) in rustfmt.iter() {
-        *count_by_key.entry(key.to_owned()).or_insert(0) += 1;
-        *count_by_pair
-            .entry((key.to_owned(), value.to_owned()))
-            .or_insert(0) += 1;
-    }
-    let mut count_by_key = count_by_key
-        .iter()
-        //.map(|pair| pair)
-        .collect::<Vec<(&String, &u32)>>();
-    #[allow(clippy::min_ident_chars)]
-    count_by_key.sort_by_key(|f| f.1);
-    count_by_key.reverse();
-
-    let mut count_by_pair = count_by_pair
-        .iter()
-        .map(|pair| (&pair.0 .0, &pair.0 .1, pair.1))
-        .collect::<Vec<(&String, &String, &u32)>>();
-    #[allow(clippy::min_ident_chars)]
-    count_by_pair.sort_by(|a, b| a.0.partial_cmp(b.0).unwrap());
-    //count_by_pair.reverse();
-
-    #[allow(clippy::pattern_type_mismatch)] // TODO
-    for (field, _count) in &count_by_key {
-        match RE_KEY.captures(field) {
-            None => {
-                log::error!("Invalid fmt key: {field}");
-                continue;
-            }
-            Some(_) => {}
-        };
-
-        let crate_names = rustfmt
-            .iter()
-            .filter(|entry| &&entry.0 == field)
-            .map(|entry| &entry.2)
-            .collect::<Vec<&String>>();
-        render_filtered_crates(
-            &format!("rustfmt/{field}"),
-            &format!("Crates using the {field} formatting option"),
-            crates,
-            |krate| crate_names.contains(&&krate.name),
-        )?;
-    }
-
-    #[allow(clippy::pattern_type_mismatch)] // TODO
-    for (field, value, _count) in &count_by_pair {
-        match RE_KEY.captures(field) {
-            None => {
-                log::error!("Invalid fmt key: {field}");
-                continue;
-            }
-            Some(_) => match RE_VALUE.captures(value) {
-                None => {
-                    log::error!("Invalid fmt value: {field}   '{value}'");
-                    continue;
-                }
-                Some(_) => {}
-            },
-        };
-
-        let crate_names = rustfmt
-            .iter()
-            .filter(|entry| &&entry.0 == field && &&entry.1 == value)
-            .map(|entry| &entry.2)
-            .collect::<Vec<&String>>();
-
-        render_filtered_crates(
-            &format!("rustfmt/{field}_{value}"),
-            &format!("Crates using the {field} formatting option set to {value}"),
-            crates,
-            |krate| crate_names.contains(&&krate.name),
-        )?;
-    }
-
-    let partials = load_templates().unwrap();
-
-    let template = liquid::ParserBuilder::with_stdlib()
-        .filter(Commafy)
-        .partials(partials)
-        .build()
-        .unwrap()
-        .parse_file("templates/rustfmt.html")
-        .unwrap();
-
-    let filename = get_site_folder().join("rustfmt/index.html");
-    let utc: DateTime<Utc> = Utc::now();
-    let globals = liquid::object!({
-        "version": format!("{VERSION}"),
-        "utc":     format!("{}", utc),
-        "title":   "Rustfmt Stats",
-        "count_by_key": count_by_key,
-        "count_by_pair": count_by_pair,
-        "stats": stats,
-        "number_of_crates": number_of_crates,
-        "with_rustfmt": stats["has_rustfmt_toml"] + stats["has_dot_rustfmt_toml"],
-    });
-    let html = template.render(&globals).unwrap();
-    let mut file = File::create(filename).unwrap();
-    writeln!(&mut file, "{html}").unwrap();
-
-    Ok(())
-}
-
 #[test]
 fn check_load_templates() {
     let _partials = load_templates();
@@ -966,3 +1873,24 @@ fn check_load_templates() {
 fn test_get_repo_types() {
     let _repos = get_repo_types();
 }
+
+#[cfg(test)]
+#[expect(
+    clippy::redundant_test_prefix,
+    reason = "named like the tests of the library"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_name() {
+        assert_eq!(page_name("max_width"), "max_width");
+        assert_eq!(page_name("clippy-pedantic"), "clippy-pedantic");
+        assert_ne!(page_name("1.70"), page_name("1-70"));
+        assert!(page_name("1.70").starts_with("1-70-"));
+        let long = format!("[{}]", vec![r#""Foo""#; 200].join(", "));
+        let name = page_name(&long);
+        assert!(name.len() < 120, "{name}");
+        assert_ne!(name, page_name(&long.replace("Foo", "Bar")));
+    }
+}
@@ -1,18 +1,31 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
-use std::error::Error;
 use std::fs;
-use std::fs::File;
-use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
+use chrono::Utc;
 use clap::Parser;
-use toml::Table;
+use toml::Value;
 
+use rust_digger::bots::UpdateBots;
+use rust_digger::ci::{ActionWarning, CiProvider, Workflows};
+use rust_digger::config::{
+    lint_attributes, parse_settings, save_settings, Config, ConfigTool, Setting,
+};
+use rust_digger::license::{find_license_files, find_license_problems, identify_license};
+use rust_digger::manifest::{
+    find_manifests, find_mismatches, load_lints, load_manifest, load_workspace, ReadFile,
+};
+use rust_digger::package::{
+    compare_with_repository, get_packages_folder, package_files, SOURCE_PACKAGE, SOURCE_REPOSITORY,
+};
+use rust_digger::readme::{find_readme, manifest_readme_path, Readme};
+use rust_digger::vcs_backend::Vcs;
 use rust_digger::{
-    collected_data_root, get_owner_and_repo, get_repos_folder, load_details, read_crates,
-    save_details, Crate, Details,
+    get_crate_subdir, get_owner_and_repo, get_package_settings_path, get_repos_folder,
+    get_settings_path, git_command, load_details, load_package_details, path_in_repository,
+    read_crates, read_latest_versions, run_command, save_details, save_package_details, Crate,
+    Details, GitError, RepoState, Version, ANALYSIS_VERSION,
 };
 
 mod macros;
@@ -20,6 +33,10 @@ use macros::ok_or_exit;
 
 #[derive(Parser, Debug)]
 #[command(version)]
+#[expect(
+    clippy::arbitrary_source_item_ordering,
+    reason = "the fields are in the order of the --help output"
+)]
 struct Cli {
     #[arg(
         long,
@@ -27,6 +44,20 @@ struct Cli {
         help = "Limit the number of repos we process."
     )]
     limit: u32,
+
+    #[arg(
+        long,
+        default_value_t = 300,
+        help = "Kill git commands after this many seconds. (0 means no limit)"
+    )]
+    git_timeout: u64,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Analyze the repositories even if their HEAD has not changed since the last analysis."
+    )]
+    force: bool,
 }
 
 fn main() {
@@ -35,20 +66,31 @@ fn main() {
     log::info!("Starting the VCS processor {}", args.limit);
 
     let crates: Vec<Crate> = ok_or_exit!(read_crates(0), 3);
-    collect_data_from_vcs(&crates, args.limit);
+    let versions = ok_or_exit!(read_latest_versions(), 3);
+    collect_data_from_vcs(&crates, &versions, &args);
 
     log::info!("Ending the VCS processor");
 }
 
-fn collect_data_from_vcs(crates: &Vec<Crate>, limit: u32) {
+fn collect_data_from_vcs(crates: &Vec<Crate>, versions: &HashMap<String, Version>, args: &Cli) {
     log::info!("process collect_data_from_vcs start");
+    let limit = args.limit;
+    let git_timeout = args.git_timeout;
     log::info!("Total number of crates: {}", crates.len());
     if 0 < limit {
         log::info!("We are going to process only {} crates", limit);
     }
 
-    let mut rustfmt: Vec<String> = vec![];
-    let mut seen: HashSet<String> = HashSet::new();
+    // keyed by (host, owner, repo) as the URLs of the crates in a workspace might point to their folders
+    let mut crates_by_repository: HashMap<(String, String, String), Vec<&Crate>> = HashMap::new();
+    for krate in crates.iter().filter(|krate| !krate.repository.is_empty()) {
+        crates_by_repository
+            .entry(get_owner_and_repo(&krate.repository))
+            .or_default()
+            .push(krate);
+    }
+
+    let mut seen: HashSet<(String, String, String)> = HashSet::new();
     let mut count: u32 = 0;
     for krate in crates {
         if 0 < limit && limit <= count {
@@ -64,16 +106,15 @@ fn collect_data_from_vcs(crates: &Vec<Crate>, limit: u32) {
             continue;
         }
 
-        let (host, owner, repo) = get_owner_and_repo(&krate.repository);
-        if owner.is_empty() {
+        let key = get_owner_and_repo(&krate.repository);
+        if key.1.is_empty() {
             continue;
         }
 
-        if seen.contains(&krate.repository.to_lowercase()) {
+        if !seen.insert(key.clone()) {
             continue;
         }
-        //log::info!("{:?}", seen);
-        seen.insert(krate.repository.to_lowercase());
+        let (host, owner, repo) = key.clone();
 
         let mut details = load_details(&krate.repository);
 
@@ -86,75 +127,268 @@ fn collect_data_from_vcs(crates: &Vec<Crate>, limit: u32) {
         env::set_current_dir(&repo_path).unwrap();
         log::info!("in folder: {:?}", env::current_dir().unwrap());
 
-        process_cargo_toml(&mut details);
+        let vcs = Vcs::from_repository(&krate.repository);
+        details.clear_vcs_error();
+        let head = get_head(vcs, git_timeout, &mut details);
+        let files = get_files(vcs, git_timeout, &mut details);
+        // a new release can change the data on Crates.io even if the repository has not changed
+        if let Some(files) = files.as_deref() {
+            let published = &crates_by_repository[&key];
+            let read = read_from_commit(vcs, files, git_timeout);
+            compare_with_crates_io(files, published, versions, &read, &mut details);
+            collect_data_about_workspace(files, published, &read, &mut details);
+        }
+        let crate_dir = get_crate_dir(&krate.repository);
+        if !args.force && details.is_analysed(&head) {
+            log::info!(
+                "HEAD {head} was already analyzed at {}",
+                details.analysed_at
+            );
+            env::set_current_dir(&current_dir).unwrap();
+            save_details(&krate.repository, &details);
+            continue;
+        }
 
-        collect_data_about_ci(&host, &mut details);
+        process_cargo_toml(&mut details, &crate_dir);
+
+        if let Some(files) = files.as_deref() {
+            collect_data_about_files(files, &crate_dir, versions.get(&krate.id), &mut details);
+        }
 
-        collect_data_about_rustfmt(&mut details, &mut rustfmt, krate);
+        let settings = collect_data_about_settings(&mut details, krate, &crate_dir);
 
         if !host.is_empty() {
-            details.commit_count = git_get_count();
+            if vcs == Vcs::Git && git_is_shallow(git_timeout) {
+                git_fetch_history(git_timeout, &mut details);
+            }
+            if let Some(commit_count) = get_count(vcs, git_timeout, &mut details) {
+                details.commit_count = commit_count;
+            }
+        }
+
+        if let Some(state) = get_repo_state(vcs, &head, files.as_deref(), git_timeout) {
+            state.as_str().clone_into(&mut details.repo_state);
         }
 
+        SOURCE_REPOSITORY.clone_into(&mut details.source);
+        details.analysed_head = head;
+        details.analysed_at = Utc::now().to_rfc3339();
+        details.analysis_version = ANALYSIS_VERSION;
+
         env::set_current_dir(&current_dir).unwrap();
         save_details(&krate.repository, &details);
+        save_repository_settings(&krate.repository, &settings);
+
+        count += 1;
+    }
+
+    collect_data_from_packages(crates, versions, args);
+}
+
+/// Run the same checks on the unpacked published packages fetched by `rust-digger-clone --packages`.
+/// If we also have a clone of the repository of the crate we compare the package with it.
+fn collect_data_from_packages(
+    crates: &Vec<Crate>,
+    versions: &HashMap<String, Version>,
+    args: &Cli,
+) {
+    log::info!("process collect_data_from_packages start");
+    let mut count: u32 = 0;
+    for krate in crates {
+        if 0 < args.limit && args.limit <= count {
+            break;
+        }
+        let package_path = get_packages_folder().join(&krate.name);
+        if !package_path.exists() {
+            continue;
+        }
+
+        let mut details = load_package_details(&krate.name);
+        let current_dir = env::current_dir().unwrap();
+        let repo_path = get_clone_path(&krate.repository).map(|path| current_dir.join(path));
+        let vcs = Vcs::from_repository(&krate.repository);
+        let repo_head = repo_path
+            .as_ref()
+            .map(|path| vcs.head_id(path, args.git_timeout).unwrap_or_default())
+            .unwrap_or_default();
+        env::set_current_dir(&package_path).unwrap();
+        log::info!("in folder: {:?}", env::current_dir().unwrap());
+
+        if !args.force
+            && details.is_analysed(&details.package_version)
+            && details.package_compared_head == repo_head
+        {
+            log::info!(
+                "Package {} {} was already analyzed at {}",
+                krate.name,
+                details.package_version,
+                details.analysed_at
+            );
+            env::set_current_dir(&current_dir).unwrap();
+            continue;
+        }
+
+        process_cargo_toml(&mut details, Path::new(""));
+        collect_data_about_files(
+            &package_files(Path::new(".")),
+            Path::new(""),
+            versions.get(&krate.id),
+            &mut details,
+        );
+        let settings = match repo_path {
+            Some(repo_path) => {
+                log::info!("compare package {} with {repo_path:?}", krate.name);
+                compare_with_repository(
+                    &mut details,
+                    Path::new("."),
+                    &repo_path,
+                    vcs,
+                    &krate.name,
+                    args.git_timeout,
+                );
+                // the settings of crates with a repository come from the repository
+                vec![]
+            }
+            None => collect_data_about_settings(&mut details, krate, Path::new("")),
+        };
+        details.package_compared_head = repo_head;
+
+        SOURCE_PACKAGE.clone_into(&mut details.source);
+        details.analysed_head.clone_from(&details.package_version);
+        details.analysed_at = Utc::now().to_rfc3339();
+        details.analysis_version = ANALYSIS_VERSION;
+
+        env::set_current_dir(&current_dir).unwrap();
+        save_package_details(&krate.name, &details);
+        save_collected_settings(&get_package_settings_path(&krate.name), &settings);
 
         count += 1;
     }
+}
+
+/// The folder of the crate in the repository, if the repository URL points to it and it has a `Cargo.toml`.
+fn get_crate_dir(repository: &str) -> PathBuf {
+    get_crate_subdir(repository)
+        .map(PathBuf::from)
+        .filter(|dir| dir.join("Cargo.toml").exists())
+        .unwrap_or_default()
+}
 
-    save_rustfm(&rustfmt);
+/// The folder of the clone of the repository, if we have one.
+fn get_clone_path(repository: &str) -> Option<PathBuf> {
+    if repository.is_empty() {
+        return None;
+    }
+    let (host, owner, repo) = get_owner_and_repo(repository);
+    if owner.is_empty() {
+        return None;
+    }
+    let path = get_repos_folder().join(host).join(owner).join(repo);
+    path.exists().then_some(path)
 }
 
-fn collect_data_about_rustfmt(details: &mut Details, rustfmt: &mut Vec<String>, krate: &Crate) {
+/// Collect the settings of rustfmt and clippy from the root of the repository and the levels of the lints in the
+/// `[lints]` table of the crate in `crate_dir`.
+fn collect_data_about_settings(
+    details: &mut Details,
+    krate: &Crate,
+    crate_dir: &Path,
+) -> Vec<Setting> {
+    let mut settings = vec![];
     details.has_rustfmt_toml = Path::new("rustfmt.toml").exists();
     details.has_dot_rustfmt_toml = Path::new(".rustfmt.toml").exists();
     if details.has_rustfmt_toml {
-        read_rustfmt(rustfmt, "rustfmt.toml", &krate.name);
+        read_settings(&mut settings, "rustfmt", "rustfmt.toml", &krate.name);
     }
     if details.has_dot_rustfmt_toml {
-        read_rustfmt(rustfmt, ".rustfmt.toml", &krate.name);
-    }
-}
-
-fn collect_data_about_ci(host: &String, details: &mut Details) {
-    if *host == "github" {
-        details.has_github_action = false;
-        let workflows = Path::new(".github/workflows");
-        if workflows.exists() {
-            for entry in workflows
-                .read_dir()
-                .expect("read_dir call failed")
-                .flatten()
-            {
-                log::info!("workflows: {:?}", entry.path());
-                details.has_github_action = true;
+        read_settings(&mut settings, "rustfmt", ".rustfmt.toml", &krate.name);
+    }
+
+    // clippy only reads the first file it finds
+    if let Some(&filename) = ConfigTool::Clippy
+        .files()
+        .iter()
+        .find(|filename| Path::new(filename).exists())
+    {
+        read_settings(&mut settings, "clippy", filename, &krate.name);
+    }
+
+    if crate_dir.join("Cargo.toml").exists() {
+        let root = env::current_dir().unwrap();
+        match load_lints(&root.join(crate_dir), &root) {
+            Ok(lints) => {
+                let file = crate_dir.join("Cargo.toml").to_string_lossy().into_owned();
+                settings.extend(lints.into_iter().map(|(lint, level)| Setting {
+                    tool: String::from("lints"),
+                    krate: krate.name.clone(),
+                    file: file.clone(),
+                    key: lint,
+                    value: Value::String(level),
+                }));
             }
+            Err(err) => log::error!("{err}"),
         }
     }
-    if *host == "gitlab" {
-        let gitlab_ci_file = Path::new(".gitlab-ci.yml");
-        details.has_gitlab_pipeline = gitlab_ci_file.exists();
-    }
+    settings
+}
+
+fn collect_data_about_ci(files: &[String], details: &mut Details) {
+    details.ci = CiProvider::from_files(files)
+        .iter()
+        .map(|provider| provider.as_str().to_owned())
+        .collect();
+    log::info!("ci: {:?}", details.ci);
+
+    let workflows = Workflows::from_files(&env::current_dir().unwrap(), files);
+    details.ci_checks = workflows
+        .checks
+        .iter()
+        .map(|check| check.as_str().to_owned())
+        .collect();
+    details.ci_os = workflows.os;
+    details.ci_cron = workflows.cron;
+    details.ci_action_warnings = workflows
+        .actions
+        .iter()
+        .filter_map(|action| {
+            let warnings = ActionWarning::from_action(action);
+            (!warnings.is_empty()).then(|| {
+                (
+                    action.clone(),
+                    warnings
+                        .iter()
+                        .map(|warning| warning.as_str().to_owned())
+                        .collect(),
+                )
+            })
+        })
+        .collect();
+    details.ci_actions = workflows.actions;
 }
 
-fn process_cargo_toml(details: &mut Details) {
+/// Collect the data from the `Cargo.toml` of the crate in `dir` (relative to the root of the repository).
+fn process_cargo_toml(details: &mut Details, dir: &Path) {
     details.cargo_toml_in_root = Path::new("Cargo.toml").exists();
 
-    match load_cargo_toml() {
-        Ok(cargo_toml) => {
-            if let Some(package) = cargo_toml.get("package") {
-                //log::info!("cargo_toml: {:#?}", package);
-                if let Some(edition) = package.get("edition") {
-                    if let Some(edition) = edition.as_str() {
-                        details.edition = edition.to_owned();
-                    }
-                };
-                if let Some(rust_version) = package.get("rust_version") {
-                    if let Some(rust_version) = rust_version.as_str() {
-                        details.rust_version = rust_version.to_owned();
-                    }
-                };
-            }
+    let root = env::current_dir().unwrap();
+    match load_manifest(&root.join(dir), &root) {
+        Ok(manifest) => {
+            details.manifest_name = manifest.name;
+            details.edition = manifest.edition;
+            details.rust_version = manifest.rust_version;
+            details.license = manifest.license;
+            details.license_file = manifest.license_file;
+            details.description = manifest.description;
+            details.categories = manifest.categories;
+            details.keywords = manifest.keywords;
+            details.readme_field = manifest.readme;
+            details.publish = manifest.publish;
+            details.features = manifest.features;
+            details.targets = manifest.targets;
+            details.build_script = manifest.build_script;
+            details.links = manifest.links;
+            details.badges = manifest.badges;
+            details.lints = manifest.lints;
         }
         Err(err) => {
             log::error!(
@@ -165,70 +399,294 @@ fn process_cargo_toml(details: &mut Details) {
     };
 }
 
-fn save_rustfm(rustfmt: &[String]) {
-    fs::create_dir_all(collected_data_root()).unwrap();
-    let filename = collected_data_root().join("rustfmt.txt");
-    let mut file = File::create(filename).unwrap();
-    for entry in rustfmt {
-        writeln!(&mut file, "{entry}").unwrap();
+fn save_repository_settings(repository: &str, settings: &[Setting]) {
+    if let Some(path) = get_settings_path(repository) {
+        save_collected_settings(&path, settings);
     }
 }
 
-fn git_get_count() -> i32 {
-    let result = Command::new("git")
-        .arg("rev-list")
-        .arg("HEAD")
-        .arg("--count")
-        .output()
-        .expect("Could not run");
-
-    if result.status.success() {
-        let stdout = core::str::from_utf8(&result.stdout).unwrap().trim_end();
-        //log::info!("'{}'", stdout);
-        let number: i32 = stdout.parse().unwrap();
-        number
-    } else {
-        0
+/// Replace the settings saved earlier in `path`.
+fn save_collected_settings(path: &Path, settings: &[Setting]) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap();
+    }
+    if let Err(err) = save_settings(path, settings) {
+        log::error!("Could not save {}: {err}", path.display());
     }
 }
 
-fn read_rustfmt(rustfmt: &mut Vec<String>, filename: &str, name: &str) {
-    match std::fs::read_to_string(filename) {
+/// Returns `None` if we could not count the commits, e.g. because git timed out.
+fn get_count(vcs: Vcs, timeout: u64, details: &mut Details) -> Option<i32> {
+    match vcs.commit_count(Path::new("."), timeout) {
+        Ok(number) => Some(number),
         Err(err) => {
-            log::error!("Error: {err} when reading {filename} of {name}");
-        }
-        Ok(content) => {
-            match content.parse::<Table>() {
-                Err(err) => {
-                    log::error!("Error: {err} when parsing toml in {filename} of {name}");
-                }
-                Ok(table) => {
-                    for row in &table {
-                        //log::debug!("key: {:30} value: {}", row.0, row.1);
-                        let mut value = row.1.to_string();
-                        if value.starts_with('"') && value.ends_with('"') {
-                            value = value[1..value.len() - 1].to_owned();
-                        }
-
-                        rustfmt.push(format!("{},{},{}", row.0, value, name));
-                    }
-                }
+            log::error!("Could not count commits: {}", err.as_str());
+            details.record_vcs_error(err, Utc::now());
+            if err == GitError::Timeout {
+                None
+            } else {
+                Some(0)
             }
         }
     }
 }
 
-fn load_cargo_toml() -> Result<Table, Box<dyn Error>> {
-    log::info!("load_cargo_toml");
-    let path = PathBuf::from("Cargo.toml");
-    if !path.exists() {
-        return Err(Box::<dyn Error>::from("Cargo.toml does not exist"));
+/// Read the files from the commit in the current folder and not from the disk, as a sparse clone has only some of them.
+fn read_from_commit(
+    vcs: Vcs,
+    files: &[String],
+    timeout: u64,
+) -> impl Fn(&Path) -> Result<String, String> + '_ {
+    let root = env::current_dir().unwrap();
+    move |path: &Path| {
+        let file = path
+            .strip_prefix(&root)
+            .map(|file| path_in_repository("", &file.to_string_lossy()))
+            .map_err(|err| format!("{} is not in the repository: {err}", path.display()))?;
+        if !files.contains(&file) {
+            return Err(format!("There is no {file} in the commit"));
+        }
+        vcs.read_file(Path::new("."), &file, timeout)
+            .map_err(|err| format!("Could not read {file}: {}", err.as_str()))
     }
-    let content = std::fs::read_to_string("Cargo.toml")?;
-    match content.parse::<Table>() {
-        Err(err) => Err(Box::<dyn Error>::from(format!(
-            "Error: {err} when parsing toml"
-        ))),
-        Ok(table) => Ok(table),
+}
+
+/// Compare the data on Crates.io of each crate in the repository with the `Cargo.toml` of the package of the same name.
+fn compare_with_crates_io(
+    files: &[String],
+    crates: &[&Crate],
+    versions: &HashMap<String, Version>,
+    read: ReadFile,
+    details: &mut Details,
+) {
+    let root = env::current_dir().unwrap();
+    let manifests = find_manifests(&root, files, read);
+
+    details.manifest_mismatches.clear();
+    for krate in crates {
+        let latest_version = versions
+            .get(&krate.id)
+            .map(|version| version.num.as_str())
+            .unwrap_or_default();
+        let mismatches = find_mismatches(krate, latest_version, manifests.get(&krate.name));
+        if !mismatches.is_empty() {
+            details.manifest_mismatches.insert(
+                krate.name.clone(),
+                mismatches
+                    .iter()
+                    .map(|mismatch| mismatch.as_str().to_owned())
+                    .collect(),
+            );
+        }
+    }
+}
+
+fn collect_data_about_update_bots(files: &[String], details: &mut Details) {
+    let bots = UpdateBots::from_files(&env::current_dir().unwrap(), files);
+    details.update_bots = bots
+        .bots
+        .iter()
+        .map(|bot| bot.as_str().to_owned())
+        .collect();
+    details.update_ecosystems = bots.ecosystems;
+    details.update_interval = bots.interval;
+}
+
+/// Collect the configuration of the tools in the root of the repository and the lint attributes of the crate in `crate_dir`.
+fn collect_data_about_config(files: &[String], crate_dir: &Path, details: &mut Details) {
+    let config = Config::from_files(&env::current_dir().unwrap(), files);
+    details.config_files = config.files;
+    details.toolchain_channel = config.toolchain.channel;
+    details.toolchain_components = config.toolchain.components;
+    details.toolchain_targets = config.toolchain.targets;
+    details.clippy_config = config.clippy;
+    details.deny_config = config.deny;
+    details.cargo_config = config.cargo_config;
+
+    details.lint_attributes = fs::read_to_string(crate_dir.join("src/lib.rs"))
+        .map(|source| lint_attributes(&source))
+        .unwrap_or_default();
+}
+
+/// Collect the data that depends on the `files` of the repository.
+fn collect_data_about_files(
+    files: &[String],
+    crate_dir: &Path,
+    latest: Option<&Version>,
+    details: &mut Details,
+) {
+    collect_data_about_ci(files, details);
+    collect_data_about_update_bots(files, details);
+    collect_data_about_config(files, crate_dir, details);
+    collect_data_about_licenses(files, crate_dir, latest, details);
+    collect_data_about_readme(files, crate_dir, details);
+}
+
+/// Identify the licenses in the license files of the crate in `crate_dir` and compare them with the license in the
+/// `Cargo.toml` (already in `details`) and the license of the `latest` release.
+fn collect_data_about_licenses(
+    files: &[String],
+    crate_dir: &Path,
+    latest: Option<&Version>,
+    details: &mut Details,
+) {
+    let license_files =
+        find_license_files(files, &crate_dir.to_string_lossy(), &details.license_file);
+    let found = license_files
+        .iter()
+        .map(|file| {
+            fs::read_to_string(file).map_or_else(
+                |err| {
+                    log::warn!("Could not read {file}: {err}");
+                    vec![]
+                },
+                |text| identify_license(&text),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    details.published_license = latest
+        .map(|version| version.license.clone())
+        .unwrap_or_default();
+    details.license_problems =
+        find_license_problems(&details.license, &found, &details.published_license)
+            .iter()
+            .map(|problem| problem.as_str().to_owned())
+            .collect();
+    details.licenses_in_files = found.into_iter().flatten().map(str::to_owned).collect();
+    details.licenses_in_files.sort();
+    details.licenses_in_files.dedup();
+    details.license_files = license_files;
+}
+
+/// Analyze the README file of the crate in `crate_dir` and check the `readme` field of the `Cargo.toml`
+/// (already in `details`).
+fn collect_data_about_readme(files: &[String], crate_dir: &Path, details: &mut Details) {
+    let crate_dir = crate_dir.to_string_lossy();
+    details.readme_missing = manifest_readme_path(&crate_dir, &details.readme_field)
+        .is_some_and(|path| !files.contains(&path));
+
+    let readme = find_readme(files, &crate_dir, &details.readme_field)
+        .and_then(|file| {
+            fs::read(&file)
+                .map_err(|err| log::warn!("Could not read {file}: {err}"))
+                .ok()
+                .map(|content| Readme::parse(&file, &String::from_utf8_lossy(&content)))
+        })
+        .unwrap_or_default();
+    details.readme_file = readme.file;
+    details.readme_format = readme.format;
+    details.readme_size = u32::try_from(readme.size).unwrap_or(u32::MAX);
+    details.readme_headings = readme.headings;
+    details.readme_has_install = readme.has_install;
+    details.readme_has_usage = readme.has_usage;
+    details.readme_has_code_example = readme.has_code_example;
+    details.readme_badges = readme
+        .badges
+        .iter()
+        .map(|badge| badge.as_str().to_owned())
+        .collect();
+}
+
+/// Collect the members of the workspace in the root of the repository and the published `crates` among them.
+fn collect_data_about_workspace(
+    files: &[String],
+    crates: &[&Crate],
+    read: ReadFile,
+    details: &mut Details,
+) {
+    let root = env::current_dir().unwrap();
+    let workspace = load_workspace(&root, files, read).unwrap_or_else(|err| {
+        log::warn!("{err}");
+        None
+    });
+
+    details.workspace = workspace.is_some();
+    let workspace = workspace.unwrap_or_default();
+    details.workspace_virtual = workspace.virtual_manifest;
+    details.workspace_crates = crates
+        .iter()
+        .filter(|krate| workspace.members.contains(&krate.name))
+        .map(|krate| krate.name.clone())
+        .collect();
+    details.workspace_crates.sort();
+    details.workspace_crates.dedup();
+    details.workspace_members = workspace.members;
+    details.workspace_lints = workspace.lints;
+    details.workspace_dependencies = workspace.dependencies;
+}
+
+/// Returns `None` if we could not list the `files`, e.g. because git timed out.
+fn get_repo_state(
+    vcs: Vcs,
+    head: &str,
+    files: Option<&[String]>,
+    timeout: u64,
+) -> Option<RepoState> {
+    if head.is_empty() {
+        return match vcs.has_commits(Path::new("."), timeout) {
+            Ok(true) => Some(RepoState::DefaultBranchMissing),
+            Ok(false) => Some(RepoState::Empty),
+            Err(_) => None,
+        };
+    }
+    files.map(RepoState::from_files)
+}
+
+/// The files in the current commit. Returns `None` if we could not list them, e.g. because git timed out.
+fn get_files(vcs: Vcs, timeout: u64, details: &mut Details) -> Option<Vec<String>> {
+    match vcs.files(Path::new("."), timeout) {
+        Ok(files) => Some(files),
+        Err(err) => {
+            log::error!("Could not list the files: {}", err.as_str());
+            details.record_vcs_error(err, Utc::now());
+            None
+        }
+    }
+}
+
+/// The SHA of the current commit or an empty string if we could not get it.
+///
+/// An empty repository has no HEAD either, so only a timeout is recorded as an error.
+fn get_head(vcs: Vcs, timeout: u64, details: &mut Details) -> String {
+    vcs.head_id(Path::new("."), timeout).unwrap_or_else(|err| {
+        if err == GitError::Timeout {
+            details.record_vcs_error(err, Utc::now());
+        }
+        String::new()
+    })
+}
+
+fn git_is_shallow(timeout: u64) -> bool {
+    let mut command = git_command();
+    command.arg("rev-parse").arg("--is-shallow-repository");
+    run_command(command, Path::new("."), timeout, 0).is_ok_and(|stdout| stdout.trim_end() == "true")
+}
+
+/// A shallow clone has only the latest commit. In order to count the commits we fetch
+/// the rest of the commits, but not the trees and files that belong to them.
+fn git_fetch_history(timeout: u64, details: &mut Details) {
+    log::info!("git fetch --unshallow --filter=tree:0");
+    let mut command = git_command();
+    command
+        .arg("fetch")
+        .arg("--unshallow")
+        .arg("--filter=tree:0");
+    if let Err(err) = run_command(command, Path::new("."), timeout, 0) {
+        details.record_vcs_error(err, Utc::now());
+    }
+}
+
+fn read_settings(settings: &mut Vec<Setting>, tool: &str, filename: &str, name: &str) {
+    match std::fs::read_to_string(filename) {
+        Err(err) => {
+            log::error!("Error: {err} when reading {filename} of {name}");
+        }
+        Ok(content) => match parse_settings(tool, name, filename, &content) {
+            Err(err) => {
+                log::error!("Error: {err} when parsing toml in {filename} of {name}");
+            }
+            Ok(found) => settings.extend(found),
+        },
     }
 }
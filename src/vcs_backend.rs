@@ -0,0 +1,544 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::{
+    get_repo_platform, git_command, normalize_repository_url, run_command, run_command_with_stderr,
+    GitError, RepoPlatform,
+};
+
+/// Fossil keeps the whole repository in a single file. We keep it inside the checkout.
+const FOSSIL_FILE: &str = ".fossil";
+
+/// What we learn about a remote repository without cloning it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Remote {
+    /// The id of the latest commit. Empty if the repository has no commits or if we have no cheap way to tell.
+    pub head: String,
+    /// The URL git was redirected to, e.g. because the repository was renamed or transferred.
+    pub redirected_url: Option<String>,
+}
+
+/// The version control systems we know how to clone, update and analyze.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Vcs {
+    Fossil,
+    Git,
+    Mercurial,
+}
+
+impl Vcs {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Git => "git",
+            Self::Mercurial => "hg",
+            Self::Fossil => "fossil",
+        }
+    }
+
+    /// Clone `url` into the `path` folder. `options` are passed to the clone command as they are.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the clone failed, took longer than `timeout` seconds or grew beyond `max_size` MB.
+    pub fn clone_repo(
+        self,
+        url: &str,
+        path: &Path,
+        options: &[&str],
+        timeout: u64,
+        max_size: u64,
+    ) -> Result<(), GitError> {
+        let mut command = self.command();
+        command.arg("clone").args(options).arg(url);
+        match self {
+            Self::Git | Self::Mercurial => {
+                command.arg(path);
+                run_command(command, path, timeout, max_size)?;
+            }
+            Self::Fossil => {
+                if let Err(err) = fs::create_dir_all(path) {
+                    log::error!("Could not create {} error: {err}", path.display());
+                    return Err(GitError::Other);
+                }
+                command.arg(path.join(FOSSIL_FILE));
+                run_command(command, path, timeout, max_size)?;
+
+                let mut command = self.command();
+                command
+                    .current_dir(path)
+                    .arg("open")
+                    .arg(FOSSIL_FILE)
+                    .arg("--force");
+                run_command(command, path, timeout, max_size)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A command of this VCS that never waits for the user.
+    pub fn command(self) -> Command {
+        match self {
+            Self::Git => git_command(),
+            Self::Mercurial => {
+                let mut command = Command::new("hg");
+                command
+                    .stdin(Stdio::null())
+                    .env("HGPLAIN", "1")
+                    .arg("--noninteractive");
+                command
+            }
+            Self::Fossil => {
+                let mut command = Command::new("fossil");
+                command.stdin(Stdio::null());
+                command
+            }
+        }
+    }
+
+    /// The number of commits leading to the checked out commit in `path`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command failed or took longer than `timeout` seconds.
+    pub fn commit_count(self, path: &Path, timeout: u64) -> Result<i32, GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            Self::Git => command.arg("rev-list").arg("HEAD").arg("--count"),
+            // print a single character for each ancestor of the current commit and count them
+            Self::Mercurial => command.arg("log").arg("-r").arg("::.").arg("-T").arg("x"),
+            // walk the parent links from the current check-in, the other branches don't count
+            Self::Fossil => {
+                let head = self.head_id(path, timeout)?;
+                if head.is_empty() || !head.chars().all(|char| char.is_ascii_hexdigit()) {
+                    return Ok(0);
+                }
+                command.arg("sql").arg(format!(
+                    "WITH RECURSIVE ancestor(rid) AS (\
+                        SELECT rid FROM blob WHERE uuid = '{head}' \
+                        UNION SELECT plink.pid FROM plink JOIN ancestor ON plink.cid = ancestor.rid\
+                    ) SELECT count(*) FROM ancestor"
+                ))
+            }
+        };
+        let stdout = run_command(command, path, timeout, 0)?;
+        let count = match self {
+            Self::Git | Self::Fossil => stdout.trim().parse().unwrap_or(0),
+            Self::Mercurial => i32::try_from(stdout.trim().len()).unwrap_or(0),
+        };
+        Ok(count)
+    }
+
+    /// The files of the checked out commit in `path` relative to the root of the repository.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command failed or took longer than `timeout` seconds.
+    pub fn files(self, path: &Path, timeout: u64) -> Result<Vec<String>, GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            // this includes the files outside of a sparse checkout
+            Self::Git => command.arg("ls-files"),
+            Self::Mercurial => command.arg("files"),
+            Self::Fossil => command.arg("ls"),
+        };
+        let stdout = run_command(command, path, timeout, 0)?;
+        Ok(stdout.lines().map(str::to_owned).collect())
+    }
+
+    /// Based on the platform of the host in `repo_types.yaml`. Everything we don't know is git.
+    pub fn from_repository(repository: &str) -> Self {
+        match get_repo_platform(repository) {
+            Some(RepoPlatform::Mercurial) => Self::Mercurial,
+            Some(RepoPlatform::Fossil) => Self::Fossil,
+            Some(
+                RepoPlatform::GitHub
+                | RepoPlatform::GitLab
+                | RepoPlatform::Gitea
+                | RepoPlatform::Cgit
+                | RepoPlatform::Forgejo
+                | RepoPlatform::Gogs,
+            )
+            | None => Self::Git,
+        }
+    }
+
+    /// Check if the commit `id` exists in the clone in `path`. (It does not have to be on any branch.)
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command took longer than `timeout` seconds.
+    pub fn has_commit(self, path: &Path, id: &str, timeout: u64) -> Result<bool, GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            // don't let a partial clone fetch the missing object from the remote
+            Self::Git => command
+                .env("GIT_NO_LAZY_FETCH", "1")
+                .arg("cat-file")
+                .arg("-e")
+                .arg(format!("{id}^{{commit}}")),
+            Self::Mercurial => command.arg("log").arg("-r").arg(id).arg("-T").arg("x"),
+            Self::Fossil => command.arg("info").arg(id),
+        };
+        match run_command(command, path, timeout, 0) {
+            Ok(_) => Ok(true),
+            Err(GitError::Timeout) => Err(GitError::Timeout),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Check if there is any commit in the clone in `path`, even if HEAD points to a missing branch.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command failed or took longer than `timeout` seconds.
+    pub fn has_commits(self, path: &Path, timeout: u64) -> Result<bool, GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            Self::Git => command.arg("rev-list").arg("--all").arg("--max-count=1"),
+            Self::Mercurial => command
+                .arg("log")
+                .arg("--limit")
+                .arg("1")
+                .arg("-T")
+                .arg("x"),
+            Self::Fossil => return Ok(self.commit_count(path, timeout)? > 0),
+        };
+        let stdout = run_command(command, path, timeout, 0)?;
+        Ok(!stdout.trim().is_empty())
+    }
+
+    /// The id (SHA) of the checked out commit in `path`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command failed or took longer than `timeout` seconds.
+    pub fn head_id(self, path: &Path, timeout: u64) -> Result<String, GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            Self::Git => command.arg("rev-parse").arg("HEAD"),
+            Self::Mercurial => command
+                .arg("log")
+                .arg("-r")
+                .arg(".")
+                .arg("-T")
+                .arg("{node}"),
+            Self::Fossil => command.arg("info"),
+        };
+        let stdout = run_command(command, path, timeout, 0)?;
+        let head = match self {
+            Self::Git | Self::Mercurial => stdout.trim(),
+            // checkout:     1f0d1f5f2b7f3a2d3e9b... 2024-03-26 10:11:12 UTC
+            Self::Fossil => stdout
+                .lines()
+                .find_map(|line| line.strip_prefix("checkout:"))
+                .and_then(|line| line.split_whitespace().next())
+                .unwrap_or_default(),
+        };
+        Ok(head.to_owned())
+    }
+
+    /// The content of the `file` (relative to the root of the repository) in the checked out commit in `path`,
+    /// even if it is outside of a sparse checkout.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file is not in the commit or the command took longer than `timeout` seconds.
+    pub fn read_file(self, path: &Path, file: &str, timeout: u64) -> Result<String, GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            Self::Git => command.arg("show").arg(format!("HEAD:{file}")),
+            Self::Mercurial => command.arg("cat").arg("-r").arg(".").arg(file),
+            Self::Fossil => command.arg("cat").arg(file),
+        };
+        run_command(command, path, timeout, 0)
+    }
+
+    /// The id of the latest commit of the remote repository without cloning it.
+    /// This also checks if the repository is reachable.
+    ///
+    /// The id is empty if the repository has no commits or if we have no cheap way to tell, e.g. for Fossil.
+    /// We only notice the redirects of git, it prints a warning when it follows one.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the repository is not reachable or the command took longer than `timeout` seconds.
+    pub fn remote_head(self, url: &str, timeout: u64) -> Result<Remote, GitError> {
+        let mut command = self.command();
+        match self {
+            Self::Git => command.arg("ls-remote").arg(url).arg("HEAD"),
+            Self::Mercurial => command
+                .arg("identify")
+                .arg("-r")
+                .arg("default")
+                .arg("-T")
+                .arg("{node}")
+                .arg(url),
+            Self::Fossil => return Ok(Remote::default()),
+        };
+        let (stdout, stderr) = run_command_with_stderr(command, Path::new("."), timeout, 0)?;
+        Ok(Remote {
+            head: stdout
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_owned(),
+            redirected_url: redirected_url(&stderr, url),
+        })
+    }
+
+    /// The names of all the tags in the clone in `path`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the command failed or took longer than `timeout` seconds.
+    pub fn tags(self, path: &Path, timeout: u64) -> Result<Vec<String>, GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            Self::Git => command.arg("tag").arg("--list"),
+            Self::Mercurial => command.arg("tags").arg("--quiet"),
+            Self::Fossil => command.arg("tag").arg("list"),
+        };
+        let stdout = run_command(command, path, timeout, 0)?;
+        Ok(stdout
+            .lines()
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    /// Fetch the new commits of the clone in `path` and update the checkout.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the update failed or took longer than `timeout` seconds.
+    pub fn update(self, path: &Path, timeout: u64) -> Result<(), GitError> {
+        let mut command = self.command();
+        command.current_dir(path);
+        match self {
+            Self::Git => command.arg("pull"),
+            Self::Mercurial => command.arg("pull").arg("--update"),
+            Self::Fossil => command.arg("update"),
+        };
+        run_command(command, path, timeout, 0)?;
+        Ok(())
+    }
+}
+
+/// The URL from the `warning: redirecting to https://github.com/owner/repo.git/` line git prints
+/// when the server redirects it. `None` if there was no redirect to another repository.
+fn redirected_url(stderr: &str, url: &str) -> Option<String> {
+    let target = stderr
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("warning: redirecting to "))?
+        .trim()
+        .trim_end_matches('/');
+    let target = target.strip_suffix(".git").unwrap_or(target);
+    (normalize_repository_url(target) != normalize_repository_url(url)).then(|| target.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_folder;
+
+    const GIT_COMMIT: &[&str] = &[
+        "-c",
+        "user.name=Foo",
+        "-c",
+        "user.email=foo@example.org",
+        "commit",
+        "--quiet",
+        "--allow-empty",
+        "-m",
+        "commit",
+    ];
+
+    fn is_installed(vcs: Vcs) -> bool {
+        let mut command = vcs.command();
+        command.arg("version");
+        run_command(command, Path::new("."), 10, 0).is_ok()
+    }
+
+    fn run(vcs: Vcs, path: &Path, args: &[&str]) {
+        let mut command = vcs.command();
+        command.current_dir(path).args(args);
+        run_command(command, path, 10, 0).unwrap();
+    }
+
+    /// Create a repository in `root/origin` with a commit and clone it to `root/clone`.
+    /// Add another commit to `origin`, update the clone and check HEAD and the number of commits.
+    /// (`init` might create `initial_commits` commits, e.g. `fossil init` creates an empty check-in.)
+    fn check_clone_and_update(
+        vcs: Vcs,
+        root: &Path,
+        url: &str,
+        init: &[&str],
+        commit: &[&[&str]],
+        initial_commits: i32,
+    ) {
+        let origin = root.join("origin");
+        fs::create_dir_all(&origin).unwrap();
+        run(vcs, &origin, init);
+        fs::write(origin.join("Cargo.toml"), "[package]\n").unwrap();
+        for args in commit {
+            run(vcs, &origin, args);
+        }
+
+        let clone = root.join("clone");
+        vcs.clone_repo(url, &clone, &[], 10, 0).unwrap();
+        assert!(clone.join("Cargo.toml").exists());
+        assert_eq!(vcs.files(&clone, 10), Ok(vec!["Cargo.toml".to_owned()]));
+        assert_eq!(
+            vcs.read_file(&clone, "Cargo.toml", 10),
+            Ok("[package]\n".to_owned())
+        );
+        vcs.read_file(&clone, "README.md", 10).unwrap_err();
+        assert_eq!(vcs.has_commits(&clone, 10), Ok(true));
+        assert_eq!(vcs.commit_count(&clone, 10), Ok(initial_commits + 1));
+        let head = vcs.head_id(&clone, 10).unwrap();
+        assert!(!head.is_empty());
+        assert_eq!(vcs.head_id(&origin, 10), Ok(head.clone()));
+
+        fs::write(origin.join("README.md"), "# Demo\n").unwrap();
+        for args in commit {
+            run(vcs, &origin, args);
+        }
+        vcs.update(&clone, 10).unwrap();
+        assert!(clone.join("README.md").exists());
+        assert_eq!(vcs.commit_count(&clone, 10), Ok(initial_commits + 2));
+        assert_ne!(vcs.head_id(&clone, 10), Ok(head));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_from_repository() {
+        assert_eq!(Vcs::from_repository("https://github.com/foo/bar"), Vcs::Git);
+        assert_eq!(
+            Vcs::from_repository("https://hg.mozilla.org/foo/bar"),
+            Vcs::Mercurial
+        );
+        assert_eq!(
+            Vcs::from_repository("https://chiselapp.com/user/foo/repository/bar"),
+            Vcs::Fossil
+        );
+        assert_eq!(
+            Vcs::from_repository("https://HG.Mozilla.org/foo/bar"),
+            Vcs::Mercurial
+        );
+        assert_eq!(Vcs::from_repository("https://zorg.com/foo/bar"), Vcs::Git);
+    }
+
+    #[test]
+    fn test_git() {
+        let root = temp_folder("git");
+        let url = root.join("origin");
+        check_clone_and_update(
+            Vcs::Git,
+            &root,
+            url.to_str().unwrap(),
+            &["init", "--quiet"],
+            &[&["add", "."], GIT_COMMIT],
+            0,
+        );
+    }
+
+    #[test]
+    fn test_git_remote_head() {
+        let vcs = Vcs::Git;
+        let origin = temp_folder("git-remote");
+        run(vcs, &origin, &["init", "--quiet"]);
+        let url = origin.to_str().unwrap();
+        assert_eq!(vcs.remote_head(url, 10), Ok(Remote::default()));
+        assert_eq!(vcs.has_commits(&origin, 10), Ok(false));
+
+        run(vcs, &origin, GIT_COMMIT);
+        assert_eq!(
+            vcs.remote_head(url, 10).map(|remote| remote.head),
+            vcs.head_id(&origin, 10)
+        );
+
+        assert_eq!(vcs.tags(&origin, 10), Ok(vec![]));
+        run(vcs, &origin, &["tag", "v0.1.0"]);
+        assert_eq!(vcs.tags(&origin, 10), Ok(vec!["v0.1.0".to_owned()]));
+
+        let head = vcs.head_id(&origin, 10).unwrap();
+        assert_eq!(vcs.has_commit(&origin, &head, 10), Ok(true));
+        assert_eq!(
+            vcs.has_commit(&origin, "1234567890123456789012345678901234567890", 10),
+            Ok(false)
+        );
+        assert_eq!(
+            vcs.remote_head(origin.join("missing").to_str().unwrap(), 10),
+            Err(GitError::NotFound)
+        );
+        fs::remove_dir_all(origin).unwrap();
+    }
+
+    #[test]
+    fn test_redirected_url() {
+        let url = "https://github.com/old/repo";
+        assert_eq!(
+            redirected_url(
+                "warning: redirecting to https://github.com/new/repo.git/\n",
+                url
+            ),
+            Some("https://github.com/new/repo".to_owned())
+        );
+        assert_eq!(
+            redirected_url(
+                "warning: redirecting to https://github.com/Old/Repo.git/\n",
+                url
+            ),
+            None
+        );
+        assert_eq!(redirected_url("", url), None);
+        assert_eq!(redirected_url("warning: templates not found\n", url), None);
+    }
+
+    #[test]
+    #[ignore = "needs Mercurial (hg); run it with --ignored"]
+    fn test_mercurial() {
+        let vcs = Vcs::Mercurial;
+        assert!(is_installed(vcs), "hg is not installed");
+        let root = temp_folder("hg");
+        let url = root.join("origin");
+        check_clone_and_update(
+            vcs,
+            &root,
+            url.to_str().unwrap(),
+            &["init"],
+            &[&["commit", "--addremove", "-u", "Foo", "-m", "commit"]],
+            0,
+        );
+    }
+
+    #[test]
+    #[ignore = "needs Fossil; run it with --ignored"]
+    fn test_fossil() {
+        let vcs = Vcs::Fossil;
+        assert!(is_installed(vcs), "fossil is not installed");
+        let root = temp_folder("fossil");
+        let repository = root.join("origin.fossil");
+        let repository = repository.to_str().unwrap();
+        run(vcs, &root, &["init", repository]);
+        check_clone_and_update(
+            vcs,
+            &root,
+            repository,
+            &["open", repository, "--force"],
+            &[&["addremove"], &["commit", "--no-warnings", "-m", "commit"]],
+            1,
+        );
+    }
+}